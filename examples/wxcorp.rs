@@ -1,6 +1,9 @@
+#[cfg(test)]
 use serde_json::json;
+#[cfg(test)]
 use wechat_vendor_sdk::wxcorp::{
     ExternalContactGroupChatGetRequestBody, ExternalContactGroupChatListRequestBody, WxcorpClient,
+    WxcorpTokenManager,
 };
 
 #[tokio::main]
@@ -14,6 +17,30 @@ async fn get_id_by_auth_code() {
     let res = wxcorp_client
         .request_user_id_by_auth_code(Some("token"), Some("code"))
         .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn token_manager() {
+    let wxcorp_client = WxcorpClient::builder()
+        .token_manager(
+            WxcorpTokenManager::new()
+                .with_agent("app", "corpid", "app secret")
+                .with_agent("contacts", "corpid", "contacts secret"),
+        )
+        .agent("app")
+        .build();
+    // 未传入 access token 时由 token 管理器获取对应应用的 access token
+    let res = wxcorp_client
+        .request_user_info_by_user_id(None, Some("nickname"))
+        .await;
+    println!("res: {:?}", res);
+
+    let res = wxcorp_client
+        .with_agent("contacts")
+        .request_external_contact_list(None, Some("xx"))
+        .await;
+    println!("res: {:?}", res);
 }
 
 #[tokio::test]
@@ -1,11 +1,18 @@
+#[cfg(test)]
+use std::sync::Arc;
+
+#[cfg(test)]
 use serde_json::json;
+#[cfg(test)]
+use wechat_vendor_sdk::token_store::FileTokenStore;
+#[cfg(test)]
 use wechat_vendor_sdk::wxmini::{
     ActionInfo, ActionScene, CreatewxaqrcodeRequestBody, GenerateSchemeJumpWxa,
     GenerateSchemeRequestBody, GenerateShortLinkRequestBody, GetwxacodeUnlimitRequestBody,
     MenuCreateRequestBody, MessageTemplateSendMiniprogramData, MessageTemplateSendRequestBody,
     QrcodeCreateRequestBody, ScanQrcodeRequestBody, StableAccessTokenRequestBody,
     TagsCreateRequestBody, TagsCreateRequestItem, TagsMembersBatchtaggingRequestBody,
-    UserInfoBatchgetItem, WxminiClient,
+    UserInfoBatchgetItem, WxminiClient, WxminiTokenManager,
 };
 
 #[tokio::main]
@@ -16,6 +23,22 @@ async fn main() {
 #[tokio::test]
 async fn without_https() {
     let wxmini_client_http = WxminiClient::without_https();
+    let res = wxmini_client_http
+        .request_access_token(Some("xx"), Some("xx"), Some("client_credential"))
+        .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn custom_base_url() {
+    // 指向本地的 mock 服务
+    let wxmini_client = WxminiClient::builder()
+        .base_url("http://127.0.0.1:8080")
+        .build();
+    let res = wxmini_client
+        .request_access_token(Some("xx"), Some("xx"), Some("client_credential"))
+        .await;
+    println!("res: {:?}", res);
 }
 
 #[tokio::test]
@@ -43,6 +66,26 @@ async fn stable_at() {
     println!("stable at: {:?}", at);
 }
 
+#[tokio::test]
+async fn token_manager() {
+    let wxmini_client = WxminiClient::builder()
+        .token_manager(WxminiTokenManager::new("xx", "yy"))
+        .build();
+    // 未传入 access token 时由 token 管理器自动获取
+    let res = wxmini_client.request_tags_get(None).await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn token_manager_with_file_store() {
+    let token_manager = WxminiTokenManager::new("xx", "yy")
+        .with_store(Arc::new(FileTokenStore::new("wxmini_tokens.json")));
+    let wxmini_client = WxminiClient::new();
+    let ticket = token_manager.jsapi_ticket(&wxmini_client).await;
+    println!("ticket: {:?}", ticket);
+}
+
+#[cfg(test)]
 const WXMINI_ACCESS_TOKEN: &str = "TOKEN_FOR_TEST";
 
 #[tokio::test]
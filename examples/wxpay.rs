@@ -1,11 +1,28 @@
-use serde_json::json;
+#[cfg(test)]
 use wechat_vendor_sdk::wxpay::api::{
     request_batch_transfer, request_close_order, request_jsapi_order,
     request_order_detail_by_out_trade_no, request_refund_detail, request_refund_order,
-    BatchTransferRequestBody, CloseOrderRequestBody, JsapiOrderAmount, JsapiOrderPayer,
-    JsapiOrderRequestBody, OrderId, OutTradeNoResponseData, RefundAmount, RefundDetailResponseData,
-    RefundOrderRequestBody, RefundOrderResponseData, TransferDetail,
+    AbnormalRefundRequestBody, AbnormalRefundType, AppOrderRequestBody, BatchTransferQuery,
+    BatchTransferRequestBody, CloseOrderRequestBody, H5OrderRequestBody, H5OrderSceneInfo, H5Type,
+    JsapiOrderAmount, JsapiOrderPayer, JsapiOrderRequestBody, NativeOrderRequestBody, OrderH5Info,
+    OrderId, RefundAmount, RefundOrderRequestBody, TransferDetail, TransferDetailStatusFilter,
 };
+#[cfg(test)]
+use wechat_vendor_sdk::wxpay::bill::{FundFlowBillQuery, TradeBillQuery, TradeBillType};
+#[cfg(test)]
+use wechat_vendor_sdk::wxpay::combine::{
+    CombineOrderAmount, CombineOrderRequestBody, CombinePayerInfo, CombineSubOrder,
+};
+#[cfg(test)]
+use wechat_vendor_sdk::wxpay::partner::{PartnerOrderPayer, PartnerOrderRequestBody};
+#[cfg(test)]
+use wechat_vendor_sdk::wxpay::profitsharing::{
+    AddReceiverRequestBody, ReceiverRelationType, ReceiverType,
+};
+#[cfg(test)]
+use wechat_vendor_sdk::wxpay::transfer::{TransferBillRequestBody, TransferSceneReportInfo};
+#[cfg(test)]
+use wechat_vendor_sdk::wxpay::WxpayClient;
 
 #[tokio::main]
 async fn main() {
@@ -147,3 +164,379 @@ async fn test_refund_detail() {
         .await;
     println!("res: {:?}", res);
 }
+
+#[cfg(test)]
+fn wxpay_client() -> WxpayClient {
+    WxpayClient::builder()
+        .mchid(read_test_file!("mchid"))
+        .mch_private_key(read_test_file!("mch_private_key"))
+        .mch_serial_no(read_test_file!("mch_serial_no"))
+        .apiv3_key(read_test_file!("apiv3_key"))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_client_order_detail() {
+    let client = wxpay_client();
+    let res = client
+        .request_order_detail_by_out_trade_no("testouttrade2")
+        .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_refresh_platform_certificates() {
+    let client = wxpay_client();
+    let res = client.refresh_platform_certificates().await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_client_native_order() {
+    let client = wxpay_client();
+    let res = client
+        .request_native_order(&NativeOrderRequestBody {
+            appid: "xxx",
+            notify_url: "https://xxx",
+            mchid: client.mchid(),
+            description: "test description",
+            out_trade_no: "testouttrade3",
+            time_expire: None,
+            attach: None,
+            goods_tag: None,
+            support_fapiao: None,
+            amount: JsapiOrderAmount {
+                total: 1,
+                currency: None,
+            },
+            detail: None,
+            scene_info: None,
+            settle_info: None,
+        })
+        .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_client_app_order() {
+    let client = wxpay_client();
+    let res = client
+        .request_app_order(&AppOrderRequestBody {
+            appid: "xxx",
+            notify_url: "https://xxx",
+            mchid: client.mchid(),
+            description: "test description",
+            out_trade_no: "testouttrade4",
+            time_expire: None,
+            attach: None,
+            goods_tag: None,
+            support_fapiao: None,
+            amount: JsapiOrderAmount {
+                total: 1,
+                currency: None,
+            },
+            detail: None,
+            scene_info: None,
+            settle_info: None,
+        })
+        .await;
+    println!("res: {:?}", res);
+    if let Ok(res) = res {
+        println!("params: {:?}", client.app_pay_params("xxx", &res.prepay_id));
+    }
+}
+
+#[tokio::test]
+async fn test_client_h5_order() {
+    let client = wxpay_client();
+    let res = client
+        .request_h5_order(&H5OrderRequestBody {
+            appid: "xxx",
+            notify_url: "https://xxx",
+            mchid: client.mchid(),
+            description: "test description",
+            out_trade_no: "testouttrade5",
+            time_expire: None,
+            attach: None,
+            goods_tag: None,
+            support_fapiao: None,
+            amount: JsapiOrderAmount {
+                total: 1,
+                currency: None,
+            },
+            detail: None,
+            scene_info: H5OrderSceneInfo {
+                payer_client_ip: "127.0.0.1",
+                device_id: None,
+                store_info: None,
+                h5_info: OrderH5Info {
+                    h5_type: H5Type::Wap,
+                    app_name: Some("test"),
+                    app_url: Some("https://xxx"),
+                    bundle_id: None,
+                    package_name: None,
+                },
+            },
+            settle_info: None,
+        })
+        .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_client_jsapi_order_with_pay_params() {
+    let client = wxpay_client();
+    let res = client
+        .request_jsapi_order_with_pay_params(&JsapiOrderRequestBody {
+            appid: "xxx",
+            notify_url: "https://xxx",
+            mchid: client.mchid(),
+            description: "test description",
+            out_trade_no: "testouttrade6",
+            time_expire: None,
+            attach: None,
+            goods_tag: None,
+            support_fapiao: None,
+            amount: JsapiOrderAmount {
+                total: 1,
+                currency: None,
+            },
+            payer: JsapiOrderPayer { openid: "xxx" },
+            detail: None,
+            scene_info: None,
+            settle_info: None,
+        })
+        .await;
+    println!(
+        "res: {:?}",
+        res.map(|params| serde_json::to_string(&params))
+    );
+}
+
+#[tokio::test]
+async fn test_client_order_detail_by_transaction_id() {
+    let client = wxpay_client();
+    let res = client
+        .request_order_detail_by_transaction_id("4200000000000000000000000000")
+        .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_client_download_trade_bill() {
+    let client = wxpay_client();
+    let res = client
+        .download_trade_bill(&TradeBillQuery {
+            bill_date: "2025-05-20",
+            bill_type: Some(TradeBillType::All),
+            gzip: true,
+        })
+        .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_client_download_fund_flow_bill() {
+    let client = wxpay_client();
+    let res = client
+        .download_fund_flow_bill(&FundFlowBillQuery {
+            bill_date: "2025-05-20",
+            account_type: None,
+            gzip: false,
+        })
+        .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_client_transfer_bill() {
+    let client = wxpay_client();
+    let res = client
+        .request_transfer_bill(&TransferBillRequestBody {
+            appid: "wxd678efh567hg6787".into(),
+            out_bill_no: "plfk2020042013".into(),
+            transfer_scene_id: "1000".into(),
+            openid: "o-MYE42l80oelYMDE34nYD456Xoy".into(),
+            user_name: None,
+            transfer_amount: 30,
+            transfer_remark: "新会员开通有礼".into(),
+            notify_url: None,
+            user_recv_perception: Some("现金奖励".into()),
+            transfer_scene_report_infos: vec![
+                TransferSceneReportInfo {
+                    info_type: "活动名称".into(),
+                    info_content: "新会员有礼".into(),
+                },
+                TransferSceneReportInfo {
+                    info_type: "奖励说明".into(),
+                    info_content: "注册会员抽奖一等奖".into(),
+                },
+            ],
+        })
+        .await;
+    println!("res: {:?}", res);
+    if let Ok(res) = res {
+        println!(
+            "params: {:?}",
+            client.merchant_transfer_params("wxd678efh567hg6787", &res)
+        );
+    }
+
+    let res = client
+        .request_transfer_bill_by_out_bill_no("plfk2020042013")
+        .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_client_query_batch_transfer() {
+    let client = wxpay_client();
+    let res = client
+        .request_batch_transfer_by_out_batch_no(
+            "testbatch1",
+            &BatchTransferQuery {
+                need_query_detail: true,
+                detail_status: Some(TransferDetailStatusFilter::All),
+                ..Default::default()
+            },
+        )
+        .await;
+    println!("res: {:?}", res);
+
+    let res = client
+        .request_transfer_detail_by_out_detail_no("testbatch1", "testbatch1detail1")
+        .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_client_apply_abnormal_refund() {
+    let client = wxpay_client();
+    let res = client
+        .request_apply_abnormal_refund(
+            "50000000382019052709732678859",
+            &AbnormalRefundRequestBody {
+                out_refund_no: "testrefund1".into(),
+                refund_type: AbnormalRefundType::UserBankCard,
+                bank_type: Some("ICBC_DEBIT".into()),
+                bank_account: Some("6222000000000000000".into()),
+                real_name: Some("张三".into()),
+            },
+        )
+        .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_client_combine_jsapi_order() {
+    let client = wxpay_client();
+    let mchid = client.mchid();
+    let sub_order = |out_trade_no, total_amount| CombineSubOrder {
+        mchid,
+        sub_mchid: None,
+        sub_appid: None,
+        attach: "test attach",
+        amount: CombineOrderAmount {
+            total_amount,
+            currency: "CNY",
+        },
+        out_trade_no,
+        description: "test description",
+        goods_tag: None,
+        settle_info: None,
+    };
+    let res = client
+        .request_combine_jsapi_order(&CombineOrderRequestBody {
+            combine_appid: "xxx",
+            combine_mchid: mchid,
+            combine_out_trade_no: "testcombine1",
+            scene_info: None,
+            sub_orders: vec![
+                sub_order("testcombine1sub1", 1),
+                sub_order("testcombine1sub2", 2),
+            ],
+            combine_payer_info: Some(CombinePayerInfo { openid: "xxx" }),
+            time_start: None,
+            time_expire: None,
+            notify_url: "https://xxx",
+        })
+        .await;
+    println!("res: {:?}", res);
+    if let Ok(res) = res {
+        println!("params: {:?}", client.jsapi_pay_params("xxx", &res));
+    }
+
+    let res = client.request_combine_order_detail("testcombine1").await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_client_partner_jsapi_order() {
+    let client = wxpay_client();
+    let res = client
+        .request_partner_jsapi_order(&PartnerOrderRequestBody {
+            sp_appid: "xxx",
+            sp_mchid: client.mchid(),
+            sub_appid: None,
+            sub_mchid: "xxx",
+            description: "test description",
+            out_trade_no: "testpartner1",
+            time_expire: None,
+            attach: None,
+            notify_url: "https://xxx",
+            goods_tag: None,
+            support_fapiao: None,
+            amount: JsapiOrderAmount {
+                total: 1,
+                currency: None,
+            },
+            payer: Some(PartnerOrderPayer {
+                sp_openid: Some("xxx"),
+                sub_openid: None,
+            }),
+            detail: None,
+            scene_info: None,
+            settle_info: None,
+        })
+        .await;
+    println!("res: {:?}", res);
+
+    let res = client
+        .request_partner_order_detail_by_out_trade_no("xxx", "testpartner1")
+        .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_client_profit_sharing() {
+    let client = wxpay_client();
+    let res = client
+        .request_add_profit_sharing_receiver(&AddReceiverRequestBody {
+            appid: "xxx".into(),
+            receiver_type: ReceiverType::PersonalOpenid,
+            account: "xxx".into(),
+            name: Some("张三".into()),
+            relation_type: ReceiverRelationType::Partner,
+            custom_relation: None,
+        })
+        .await;
+    println!("res: {:?}", res);
+
+    let res = client
+        .request_profit_sharing_unsplit_amount("4208450740201411110007820472")
+        .await;
+    println!("res: {:?}", res);
+}
+
+#[tokio::test]
+async fn test_client_upload_image() {
+    let client = wxpay_client();
+    let content = std::fs::read(format!(
+        "{}/wxpay_local_test_data/test.png",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+    let res = client.upload_image("test.png", &content).await;
+    println!("res: {:?}", res);
+}
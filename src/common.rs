@@ -1,5 +1,7 @@
 //! 存放公用内容
 //!
+use std::time::Duration;
+
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -7,3 +9,88 @@ pub struct AccessTokenData {
     pub access_token: String,
     pub expires_in: u64,
 }
+
+/// 用于构造 [`reqwest::Client`] 的常用配置，各模块的 client 都可以复用同一个构造出的 http client
+///
+/// ```ignore
+/// let http_client = HttpClientConfig::builder()
+///     .connect_timeout(Duration::from_secs(3))
+///     .timeout(Duration::from_secs(10))
+///     .proxy(reqwest::Proxy::all("http://127.0.0.1:7890")?)
+///     .build()
+///     .build_client()?;
+/// let client = WxminiClient::builder().http_client(http_client).build();
+/// ```
+#[derive(Debug, Default, bon::Builder)]
+pub struct HttpClientConfig {
+    /// 建立连接的超时时间
+    pub connect_timeout: Option<Duration>,
+    /// 整个请求（包含读取响应）的超时时间
+    pub timeout: Option<Duration>,
+    /// 出口代理
+    pub proxy: Option<reqwest::Proxy>,
+    /// 连接池中空闲连接的保留时间
+    pub pool_idle_timeout: Option<Duration>,
+    /// 连接池中每个 host 最多保留的空闲连接数
+    pub pool_max_idle_per_host: Option<usize>,
+    /// 额外信任的根证书
+    #[builder(default)]
+    pub root_certificates: Vec<reqwest::Certificate>,
+}
+
+impl HttpClientConfig {
+    pub fn build_client(&self) -> Result<reqwest::Client, reqwest::Error> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+        if let Some(pool_idle_timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(pool_idle_timeout);
+        }
+        if let Some(pool_max_idle_per_host) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(pool_max_idle_per_host);
+        }
+        for cert in &self.root_certificates {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        builder.build()
+    }
+}
+
+/// 未指定 http client 时使用的进程内共享 client，`reqwest::Client` 内部是 Arc，clone 后共享同一个连接池
+#[cfg(any(feature = "wxcorp", feature = "wxmini", feature = "wxpay"))]
+pub(crate) fn default_http_client() -> reqwest::Client {
+    static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new).clone()
+}
+
+/// 使用 `access_token` 替换 query 中未传入的 access_token 参数
+#[cfg(any(feature = "wxcorp", feature = "wxmini"))]
+pub(crate) fn with_access_token<'a>(
+    query: &[(&'a str, Option<&'a str>)],
+    access_token: Option<&'a str>,
+) -> Vec<(&'a str, Option<&'a str>)> {
+    query
+        .iter()
+        .map(|&(k, v)| match (k, v, access_token) {
+            ("access_token", None, Some(token)) => (k, Some(token)),
+            _ => (k, v),
+        })
+        .collect()
+}
+
+/// 在测试中运行异步代码，tokio 的宏没有在普通依赖中启用
+#[cfg(all(test, any(feature = "wxcorp", feature = "wxmini", feature = "wxpay")))]
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
@@ -4,6 +4,14 @@
 
 pub mod common;
 
+/// 实现 [`token_store::TokenStore`]、[`wxpay::reconcile::LocalOrderStore`] 等异步 trait 时使用
+#[cfg(any(feature = "wxcorp", feature = "wxmini", feature = "wxpay"))]
+pub use async_trait::async_trait;
+
+#[cfg(any(feature = "wxcorp", feature = "wxmini"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "wxcorp", feature = "wxmini"))))]
+pub mod token_store;
+
 #[cfg(feature = "wxcorp")]
 #[cfg_attr(docsrs, doc(cfg(feature = "wxcorp")))]
 pub mod wxcorp;
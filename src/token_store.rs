@@ -0,0 +1,439 @@
+//! access token、jsapi ticket 等凭证的缓存存储
+//!
+//! 多个服务实例共用同一个 appid 时，各自获取 access token 会导致彼此的 token 失效，
+//! 此时可以实现 [`TokenStore`] 将凭证存放在 redis、数据库等共享存储中：
+//! ```ignore
+//! use wechat_vendor_sdk::async_trait;
+//! use wechat_vendor_sdk::token_store::{TokenStore, TokenStoreError};
+//!
+//! struct RedisTokenStore { /* ... */ }
+//!
+//! #[async_trait]
+//! impl TokenStore for RedisTokenStore {
+//!     async fn get(&self, key: &str) -> Result<Option<String>, TokenStoreError> { todo!() }
+//!     async fn set(&self, key: &str, value: &str, expires_in: Duration) -> Result<(), TokenStoreError> { todo!() }
+//!     // 使用 `SET key value NX PX ttl` 实现跨进程的锁
+//!     async fn lock(&self, key: &str, ttl: Duration) -> Result<bool, TokenStoreError> { todo!() }
+//!     async fn unlock(&self, key: &str) -> Result<(), TokenStoreError> { todo!() }
+//! }
+//!
+//! let store = Arc::new(RedisTokenStore {});
+//! let wxmini_manager = WxminiTokenManager::new(appid, secret).with_store(store.clone());
+//! let wxcorp_manager = WxcorpTokenManager::new().with_store(store);
+//! ```
+//!
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+/// 存储实现返回的错误
+#[derive(Error, Debug)]
+#[error("token store error: {0}")]
+pub struct TokenStoreError(Box<dyn std::error::Error + Send + Sync>);
+
+impl TokenStoreError {
+    pub fn new(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self(err.into())
+    }
+}
+
+#[async_trait]
+pub trait TokenStore: Send + Sync {
+    /// 读取未过期的凭证
+    async fn get(&self, key: &str) -> Result<Option<String>, TokenStoreError>;
+
+    /// 写入凭证，`expires_in` 之后视为过期
+    async fn set(
+        &self,
+        key: &str,
+        value: &str,
+        expires_in: Duration,
+    ) -> Result<(), TokenStoreError>;
+
+    /// 尝试获取跨进程的刷新锁，返回是否获取成功，`ttl` 后锁自动释放。
+    ///
+    /// 默认实现总是成功，即只在进程内合并刷新请求
+    async fn lock(&self, _key: &str, _ttl: Duration) -> Result<bool, TokenStoreError> {
+        Ok(true)
+    }
+
+    /// 释放刷新锁
+    async fn unlock(&self, _key: &str) -> Result<(), TokenStoreError> {
+        Ok(())
+    }
+}
+
+/// 进程内的内存存储，token 管理器未指定存储时默认使用
+#[derive(Default)]
+pub struct MemoryTokenStore {
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TokenStore for MemoryTokenStore {
+    async fn get(&self, key: &str) -> Result<Option<String>, TokenStoreError> {
+        let entries = self.entries.lock().await;
+        Ok(entries
+            .get(key)
+            .filter(|(_, expires_at)| *expires_at > Instant::now())
+            .map(|(value, _)| value.clone()))
+    }
+
+    async fn set(
+        &self,
+        key: &str,
+        value: &str,
+        expires_in: Duration,
+    ) -> Result<(), TokenStoreError> {
+        let mut entries = self.entries.lock().await;
+        entries.insert(key.into(), (value.into(), Instant::now() + expires_in));
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FileTokenEntry {
+    value: String,
+    /// 过期时间的 unix 时间戳（秒）
+    expires_at: u64,
+}
+
+/// 以 json 文件存储凭证，适合单机部署时在进程重启后复用未过期的凭证。
+///
+/// 只在进程内互斥读写文件，多个进程共用同一文件时请使用支持 [`TokenStore::lock`] 的存储
+pub struct FileTokenStore {
+    path: PathBuf,
+    file_lock: Mutex<()>,
+}
+
+impl FileTokenStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            file_lock: Mutex::new(()),
+        }
+    }
+
+    async fn read_entries(&self) -> Result<HashMap<String, FileTokenEntry>, TokenStoreError> {
+        match tokio::fs::read(&self.path).await {
+            Ok(content) => serde_json::from_slice(&content).map_err(TokenStoreError::new),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(err) => Err(TokenStoreError::new(err)),
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[async_trait]
+impl TokenStore for FileTokenStore {
+    async fn get(&self, key: &str) -> Result<Option<String>, TokenStoreError> {
+        let _guard = self.file_lock.lock().await;
+        let entries = self.read_entries().await?;
+        Ok(entries
+            .get(key)
+            .filter(|entry| entry.expires_at > unix_now())
+            .map(|entry| entry.value.clone()))
+    }
+
+    async fn set(
+        &self,
+        key: &str,
+        value: &str,
+        expires_in: Duration,
+    ) -> Result<(), TokenStoreError> {
+        let _guard = self.file_lock.lock().await;
+        let mut entries = self.read_entries().await?;
+        let now = unix_now();
+        entries.retain(|_, entry| entry.expires_at > now);
+        entries.insert(
+            key.into(),
+            FileTokenEntry {
+                value: value.into(),
+                expires_at: now + expires_in.as_secs(),
+            },
+        );
+        let content = serde_json::to_vec_pretty(&entries).map_err(TokenStoreError::new)?;
+        tokio::fs::write(&self.path, content)
+            .await
+            .map_err(TokenStoreError::new)
+    }
+}
+
+/// 用于测试的存储，时间只会通过 [`MockTokenStore::advance`] 推进，可以离线验证凭证过期后的行为
+#[derive(Default)]
+pub struct MockTokenStore {
+    /// key -> (value, 过期时刻)
+    entries: std::sync::Mutex<HashMap<String, (String, Duration)>>,
+    locks: std::sync::Mutex<HashMap<String, Duration>>,
+    now: std::sync::Mutex<Duration>,
+}
+
+impl MockTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 推进模拟时间
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+
+    /// 读取凭证的剩余有效时间，已过期或不存在时返回 None
+    pub fn ttl(&self, key: &str) -> Option<Duration> {
+        let now = *self.now.lock().unwrap();
+        self.entries
+            .lock()
+            .unwrap()
+            .get(key)
+            .and_then(|(_, expires_at)| expires_at.checked_sub(now))
+            .filter(|ttl| !ttl.is_zero())
+    }
+}
+
+#[async_trait]
+impl TokenStore for MockTokenStore {
+    async fn get(&self, key: &str) -> Result<Option<String>, TokenStoreError> {
+        let now = *self.now.lock().unwrap();
+        Ok(self
+            .entries
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|(_, expires_at)| *expires_at > now)
+            .map(|(value, _)| value.clone()))
+    }
+
+    async fn set(
+        &self,
+        key: &str,
+        value: &str,
+        expires_in: Duration,
+    ) -> Result<(), TokenStoreError> {
+        let now = *self.now.lock().unwrap();
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.into(), (value.into(), now + expires_in));
+        Ok(())
+    }
+
+    async fn lock(&self, key: &str, ttl: Duration) -> Result<bool, TokenStoreError> {
+        let now = *self.now.lock().unwrap();
+        let mut locks = self.locks.lock().unwrap();
+        if locks.get(key).is_some_and(|expires_at| *expires_at > now) {
+            return Ok(false);
+        }
+        locks.insert(key.into(), now + ttl);
+        Ok(true)
+    }
+
+    async fn unlock(&self, key: &str) -> Result<(), TokenStoreError> {
+        self.locks.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+/// 在凭证过期前多久就视为过期并重新获取
+const REFRESH_AHEAD: Duration = Duration::from_secs(300);
+
+/// 跨进程刷新锁的持有时间，获取锁失败时最多等待这么久
+const REFRESH_LOCK_TTL: Duration = Duration::from_secs(10);
+
+/// 等待其他进程刷新时，轮询存储的间隔
+const REFRESH_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 从存储中读取凭证，不存在或与调用方确认已失效的 `stale` 相同时，调用 `fetch` 重新获取并写入存储。
+///
+/// `local_lock` 用于合并进程内并发的刷新，[`TokenStore::lock`] 用于合并跨进程的刷新。
+/// `fetch` 返回凭证和有效时间（秒）
+pub(crate) async fn get_or_fetch_token<E, F, Fut>(
+    store: &dyn TokenStore,
+    local_lock: &Mutex<()>,
+    key: &str,
+    stale: Option<&str>,
+    fetch: F,
+) -> Result<String, E>
+where
+    E: From<TokenStoreError>,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<(String, u64), E>>,
+{
+    let is_usable =
+        |token: &Option<String>| token.as_deref().is_some_and(|token| stale != Some(token));
+
+    let token = store.get(key).await?;
+    if is_usable(&token) {
+        return Ok(token.unwrap());
+    }
+
+    let _guard = local_lock.lock().await;
+    // 等待锁期间可能已经被其他调用刷新过
+    let token = store.get(key).await?;
+    if is_usable(&token) {
+        return Ok(token.unwrap());
+    }
+
+    let lock_key = format!("{}:lock", key);
+    let locked = store.lock(&lock_key, REFRESH_LOCK_TTL).await?;
+    if !locked {
+        // 其他进程正在刷新，等待其写入存储；超时后自行刷新
+        let deadline = Instant::now() + REFRESH_LOCK_TTL;
+        while Instant::now() < deadline {
+            tokio::time::sleep(REFRESH_POLL_INTERVAL).await;
+            let token = store.get(key).await?;
+            if is_usable(&token) {
+                return Ok(token.unwrap());
+            }
+        }
+    }
+
+    let result = fetch().await;
+    let stored = match &result {
+        Ok((token, expires_in)) => {
+            let ttl = Duration::from_secs(*expires_in).saturating_sub(REFRESH_AHEAD);
+            store.set(key, token, ttl).await
+        }
+        Err(_) => Ok(()),
+    };
+    // 只释放自己持有的锁，等待超时后自行刷新时锁仍属于其他进程
+    let unlocked = if locked {
+        store.unlock(&lock_key).await
+    } else {
+        Ok(())
+    };
+    stored?;
+    unlocked?;
+    result.map(|(token, _)| token)
+}
+
+#[cfg(test)]
+use crate::common::block_on;
+
+#[test]
+fn test_mock_token_store_expiry() {
+    block_on(async {
+        let store = MockTokenStore::new();
+        store
+            .set("key", "token", Duration::from_secs(7200))
+            .await
+            .unwrap();
+        assert_eq!(store.get("key").await.unwrap().as_deref(), Some("token"));
+
+        store.advance(Duration::from_secs(7199));
+        assert_eq!(store.ttl("key"), Some(Duration::from_secs(1)));
+        assert_eq!(store.get("key").await.unwrap().as_deref(), Some("token"));
+
+        store.advance(Duration::from_secs(1));
+        assert_eq!(store.ttl("key"), None);
+        assert_eq!(store.get("key").await.unwrap(), None);
+    });
+}
+
+#[test]
+fn test_get_or_fetch_token() {
+    block_on(async {
+        let store = MockTokenStore::new();
+        let local_lock = Mutex::new(());
+        let fetch_count = std::sync::atomic::AtomicU32::new(0);
+        let fetch = || async {
+            let count = fetch_count.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            Ok::<_, TokenStoreError>((format!("token{}", count), 7200))
+        };
+
+        let token = get_or_fetch_token(&store, &local_lock, "key", None, fetch)
+            .await
+            .unwrap();
+        assert_eq!(token, "token1");
+        // 提前 REFRESH_AHEAD 过期
+        assert_eq!(
+            store.ttl("key"),
+            Some(Duration::from_secs(7200) - REFRESH_AHEAD)
+        );
+
+        // 缓存未过期时不会重新获取
+        let token = get_or_fetch_token(&store, &local_lock, "key", None, fetch)
+            .await
+            .unwrap();
+        assert_eq!(token, "token1");
+
+        // 调用方确认 token 失效时强制重新获取
+        let token = get_or_fetch_token(&store, &local_lock, "key", Some("token1"), fetch)
+            .await
+            .unwrap();
+        assert_eq!(token, "token2");
+
+        // 已被其他调用刷新过时，不会再次获取
+        let token = get_or_fetch_token(&store, &local_lock, "key", Some("token1"), fetch)
+            .await
+            .unwrap();
+        assert_eq!(token, "token2");
+
+        store.advance(Duration::from_secs(7200));
+        let token = get_or_fetch_token(&store, &local_lock, "key", None, fetch)
+            .await
+            .unwrap();
+        assert_eq!(token, "token3");
+    });
+}
+
+/// 写入总是失败的存储，用于验证写入失败时仍会释放刷新锁
+#[cfg(test)]
+struct FailingSetTokenStore(MockTokenStore);
+
+#[cfg(test)]
+#[async_trait]
+impl TokenStore for FailingSetTokenStore {
+    async fn get(&self, key: &str) -> Result<Option<String>, TokenStoreError> {
+        self.0.get(key).await
+    }
+
+    async fn set(
+        &self,
+        _key: &str,
+        _value: &str,
+        _expires_in: Duration,
+    ) -> Result<(), TokenStoreError> {
+        Err(TokenStoreError::new("set failed"))
+    }
+
+    async fn lock(&self, key: &str, ttl: Duration) -> Result<bool, TokenStoreError> {
+        self.0.lock(key, ttl).await
+    }
+
+    async fn unlock(&self, key: &str) -> Result<(), TokenStoreError> {
+        self.0.unlock(key).await
+    }
+}
+
+#[test]
+fn test_get_or_fetch_token_unlocks_on_set_error() {
+    block_on(async {
+        let store = FailingSetTokenStore(MockTokenStore::new());
+        let local_lock = Mutex::new(());
+        let fetch = || async { Ok::<_, TokenStoreError>(("token1".to_string(), 7200)) };
+
+        let result = get_or_fetch_token(&store, &local_lock, "key", None, fetch).await;
+        assert!(result.is_err());
+        assert!(store.lock("key:lock", REFRESH_LOCK_TTL).await.unwrap());
+    });
+}
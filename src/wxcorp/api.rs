@@ -10,7 +10,7 @@ use crate::{common::AccessTokenData, wxcorp_api_get, wxcorp_api_post};
 wxcorp_api_get!(
     /// [获取 access token](https://developer.work.weixin.qq.com/document/path/91039)
     request_access_token,
-    "https://qyapi.weixin.qq.com/cgi-bin/gettoken",
+    "/cgi-bin/gettoken",
     (corpid: Option<&str>, corpsecret: Option<&str>),
     AccessTokenData
 );
@@ -30,7 +30,7 @@ pub enum UserIdInfoByAuthCode {
 wxcorp_api_get!(
     /// [根据 code 获取用户 id 信息](https://developer.work.weixin.qq.com/document/path/98176)
     request_user_id_by_auth_code,
-    "https://qyapi.weixin.qq.com/cgi-bin/user/getuserinfo",
+    "/cgi-bin/user/getuserinfo",
     (access_token: Option<&str>, code: Option<&str>),
     UserIdInfoByAuthCode
 );
@@ -47,14 +47,14 @@ pub struct UserInfo {
 wxcorp_api_get!(
     /// [根据 userid 获取用户详情](https://developer.work.weixin.qq.com/document/path/90196)
     request_user_info_by_user_id,
-    "https://qyapi.weixin.qq.com/cgi-bin/user/get",
+    "/cgi-bin/user/get",
     (access_token: Option<&str>, userid: Option<&str>),
     UserInfo
 );
 
 wxcorp_api_post!(
     request_send,
-    "https://qyapi.weixin.qq.com/cgi-bin/message/send",
+    "/cgi-bin/message/send",
     (access_token: Option<&str>),
     &Value,
     Value
@@ -62,7 +62,7 @@ wxcorp_api_post!(
 
 wxcorp_api_post!(
     request_webhook_send,
-    "https://qyapi.weixin.qq.com/cgi-bin/webhook/send",
+    "/cgi-bin/webhook/send",
     (key: Option<&str>),
     &Value,
     Value
@@ -75,7 +75,7 @@ pub struct ExternalContactListResponseData {
 wxcorp_api_get!(
     /// [获取客户列表](https://developer.work.weixin.qq.com/document/path/92113)
     request_external_contact_list,
-    "https://qyapi.weixin.qq.com/cgi-bin/externalcontact/list",
+    "/cgi-bin/externalcontact/list",
     (access_token: Option<&str>,  userid: Option<&str>),
     ExternalContactListResponseData
 );
@@ -89,7 +89,7 @@ pub struct ExternalContactGetResponseData {
 wxcorp_api_get!(
     /// [获取客户详情](https://developer.work.weixin.qq.com/document/path/92114)
     request_external_contact_get,
-    "https://qyapi.weixin.qq.com/cgi-bin/externalcontact/get",
+    "/cgi-bin/externalcontact/get",
     (access_token: Option<&str>,  external_userid: Option<&str>, cursor: Option<&str>),
     ExternalContactGetResponseData
 );
@@ -110,7 +110,7 @@ pub struct ExternalContactBatchGetByUserResponseData {
 wxcorp_api_post!(
     /// [批量获取客户详情](https://developer.work.weixin.qq.com/document/path/92994)
     request_external_contact_batch_get_by_user,
-    "https://qyapi.weixin.qq.com/cgi-bin/externalcontact/batch/get_by_user",
+    "/cgi-bin/externalcontact/batch/get_by_user",
     (access_token: Option<&str>),
     &ExternalContactBatchGetByUserRequestBody,
     ExternalContactBatchGetByUserResponseData
@@ -135,7 +135,7 @@ pub struct ExternalContactGroupChatListResponseData {
 }
 wxcorp_api_post!(
     request_external_contact_group_chat_list,
-    "https://qyapi.weixin.qq.com/cgi-bin/externalcontact/groupchat/list",
+    "/cgi-bin/externalcontact/groupchat/list",
     (access_token: Option<&str>),
     &ExternalContactGroupChatListRequestBody,
     ExternalContactGroupChatListResponseData
@@ -153,7 +153,7 @@ pub struct ExternalContactGroupChatGetResponseData {
 }
 wxcorp_api_post!(
     request_external_contact_group_chat_get,
-    "https://qyapi.weixin.qq.com/cgi-bin/externalcontact/groupchat/get",
+    "/cgi-bin/externalcontact/groupchat/get",
     (access_token: Option<&str>),
     &ExternalContactGroupChatGetRequestBody,
     ExternalContactGroupChatGetResponseData
@@ -1,11 +1,14 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
 use serde::Serialize;
 use serde_json::Value;
-use tracing::error;
 
 use thiserror::Error;
 
+use super::token::WxcorpTokenManager;
+use crate::common::{default_http_client, with_access_token};
+use crate::token_store::TokenStoreError;
+
 #[derive(Error, Debug)]
 pub enum WxcorpApiError {
     #[error("bad errorcode: {0}")]
@@ -14,20 +17,61 @@ pub enum WxcorpApiError {
     WxcorpResDeserializeErr(#[from] serde_json::Error),
     #[error("request error: {0}")]
     RequestErr(#[from] reqwest::Error),
+    #[error("{0}")]
+    TokenStoreErr(#[from] TokenStoreError),
+    /// 应用未在 token 管理器中注册
+    #[error("unknown agent: {0}")]
+    UnknownAgent(String),
 }
 
-pub struct WxcorpClient {}
+/// 企业微信 api 的默认请求地址
+pub const WXCORP_DEFAULT_BASE_URL: &str = "https://qyapi.weixin.qq.com";
+
+#[derive(Clone, bon::Builder)]
+pub struct WxcorpClient {
+    /// 请求的协议和域名，不以 `/` 结尾，默认为 [`WXCORP_DEFAULT_BASE_URL`]
+    #[builder(into, default = WXCORP_DEFAULT_BASE_URL.to_string())]
+    base_url: String,
+    /// 发起请求所用的 http client，所有 api 调用都会复用它（以及它的连接池），
+    /// 可以通过 [`HttpClientConfig`](crate::common::HttpClientConfig) 构造带超时、代理等配置的 client。默认使用进程内共享的 client
+    #[builder(default = default_http_client())]
+    http_client: reqwest::Client,
+    /// 设置后，调用需要 access token 的接口时如果未传入 access token，
+    /// 会自动从管理器中获取 `agent` 对应应用的 access token，并在失效时刷新后重试一次
+    #[builder(into)]
+    token_manager: Option<Arc<WxcorpTokenManager>>,
+    /// 当前使用的应用名称，需要先在 token 管理器中注册
+    #[builder(into)]
+    agent: Option<String>,
+}
+
+impl Default for WxcorpClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl WxcorpClient {
     pub fn new() -> Self {
-        Self {}
+        Self::builder().build()
+    }
+
+    /// 返回使用指定应用 access token 的 client，和原 client 共用 http client 及 token 管理器
+    /// ```ignore
+    /// let res_data = client.with_agent("contacts").request_external_contact_list(None, Some(userid)).await;
+    /// ```
+    pub fn with_agent(&self, agent: impl Into<String>) -> Self {
+        Self {
+            agent: Some(agent.into()),
+            ..self.clone()
+        }
     }
 }
 
 impl WxcorpClient {
     pub(crate) async fn call_get<D, F>(
         &self,
-        url: &str,
+        path: &str,
         // TODO: 待改造为 bon
         query: &[(&str, Option<&str>)],
         map: F,
@@ -35,58 +79,143 @@ impl WxcorpClient {
     where
         F: FnOnce(Value) -> Result<D, serde_json::Error>,
     {
-        let client = reqwest::Client::new();
-        let response = client.get(url).query(query).send().await?;
+        let token = self.managed_access_token(query).await?;
+        let data = match (self.get_value(path, query, token.as_deref()).await, token) {
+            (Err(err), Some(token)) if is_access_token_invalid(&err) => {
+                let token = self.refresh_managed_access_token(&token).await?;
+                self.get_value(path, query, Some(&token)).await?
+            }
+            (result, _) => result?,
+        };
+
+        match map(data) {
+            Ok(data) => Ok(data),
+            Err(err) => Err(WxcorpApiError::WxcorpResDeserializeErr(err)),
+        }
+    }
+
+    pub(crate) async fn call_post<D, B, F>(
+        &self,
+        path: &str,
+        query: &[(&str, Option<&str>)],
+        body: &B,
+        map: F,
+    ) -> Result<D, WxcorpApiError>
+    where
+        B: Serialize + Debug,
+        F: FnOnce(Value) -> Result<D, serde_json::Error>,
+    {
+        let token = self.managed_access_token(query).await?;
+        let data = match (
+            self.post_value(path, query, body, token.as_deref()).await,
+            token,
+        ) {
+            (Err(err), Some(token)) if is_access_token_invalid(&err) => {
+                let token = self.refresh_managed_access_token(&token).await?;
+                self.post_value(path, query, body, Some(&token)).await?
+            }
+            (result, _) => result?,
+        };
+
+        match map(data) {
+            Ok(data) => Ok(data),
+            Err(err) => Err(WxcorpApiError::WxcorpResDeserializeErr(err)),
+        }
+    }
+
+    /// 接口需要 access token 但调用方未传入时，从 token 管理器中获取当前应用的 access token
+    async fn managed_access_token(
+        &self,
+        query: &[(&str, Option<&str>)],
+    ) -> Result<Option<String>, WxcorpApiError> {
+        match (&self.token_manager, &self.agent) {
+            (Some(manager), Some(agent)) if query.contains(&("access_token", None)) => {
+                Ok(Some(manager.agent_access_token(self, agent).await?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn refresh_managed_access_token(&self, stale: &str) -> Result<String, WxcorpApiError> {
+        let (Some(manager), Some(agent)) = (&self.token_manager, &self.agent) else {
+            unreachable!("managed access token should come from token manager");
+        };
+        manager.refresh_agent_access_token(self, agent, stale).await
+    }
+
+    pub(crate) async fn get_value(
+        &self,
+        path: &str,
+        query: &[(&str, Option<&str>)],
+        access_token: Option<&str>,
+    ) -> Result<Value, WxcorpApiError> {
+        let response = self
+            .http_client
+            .get(format!("{}{}", self.base_url, path))
+            .query(&with_access_token(query, access_token))
+            .send()
+            .await?;
         let data: Value = response.json().await?;
         // trace!("wxcorp api response: {:?}", data);
 
         if data["errcode"] == 0 {
-            match map(data) {
-                Ok(data) => Ok(data),
-                Err(err) => Err(WxcorpApiError::WxcorpResDeserializeErr(err)),
-            }
+            Ok(data)
         } else {
             Err(WxcorpApiError::ApiCodeNotOk(data))
         }
     }
 
-    pub(crate) async fn call_post<D, B, F>(
+    pub(crate) async fn post_value<B>(
         &self,
-        url: &str,
+        path: &str,
         query: &[(&str, Option<&str>)],
         body: &B,
-        map: F,
-    ) -> Result<D, WxcorpApiError>
+        access_token: Option<&str>,
+    ) -> Result<Value, WxcorpApiError>
     where
         B: Serialize + Debug,
-        F: FnOnce(Value) -> Result<D, serde_json::Error>,
     {
-        let client = reqwest::Client::new();
-        let response = client.post(url).query(query).json(body).send().await?;
+        let response = self
+            .http_client
+            .post(format!("{}{}", self.base_url, path))
+            .query(&with_access_token(query, access_token))
+            .json(body)
+            .send()
+            .await?;
 
         let data: Value = response.json().await?;
         // trace!("wxcorp api post response: {:?}", data);
 
         if data["errcode"] == 0 {
-            match map(data) {
-                Ok(data) => Ok(data),
-                Err(err) => Err(WxcorpApiError::WxcorpResDeserializeErr(err)),
-            }
+            Ok(data)
         } else {
             Err(WxcorpApiError::ApiCodeNotOk(data))
         }
     }
 }
 
+/// access token 失效或过期的错误码
+const INVALID_ACCESS_TOKEN_ERRCODES: [i64; 2] = [40014, 42001];
+
+fn is_access_token_invalid(err: &WxcorpApiError) -> bool {
+    match err {
+        WxcorpApiError::ApiCodeNotOk(data) => data
+            .get("errcode")
+            .and_then(|v| v.as_i64())
+            .is_some_and(|code| INVALID_ACCESS_TOKEN_ERRCODES.contains(&code)),
+        _ => false,
+    }
+}
+
 #[macro_export]
 macro_rules! wxcorp_api_get {
-    ($(#[$attr:meta])* $name: ident, $url: tt, ($($v:ident: $t:ty),*), $ret_type: ty) => {
+    ($(#[$attr:meta])* $name: ident, $path: tt, ($($v:ident: $t:ty),*), $ret_type: ty) => {
         impl $crate::wxcorp::WxcorpClient {
             $(#[$attr])*
             pub async fn $name(&self, $($v: $t),*) -> Result<$ret_type, $crate::wxcorp::WxcorpApiError> {
                 self.call_get(
                     &format!(
-                        $url
+                        $path
                     ),
                     // stringify! 将 ident 转为字符串形式
                     &[$((stringify!($v), $v)),*],
@@ -100,12 +229,12 @@ macro_rules! wxcorp_api_get {
 
 #[macro_export]
 macro_rules! wxcorp_api_post {
-    ($(#[$attr:meta])* $name: ident, $url: tt, ($($v:ident: $t:ty),*), $req_body:ty, $ret_type:ty) => {
+    ($(#[$attr:meta])* $name: ident, $path: tt, ($($v:ident: $t:ty),*), $req_body:ty, $ret_type:ty) => {
         impl $crate::wxcorp::WxcorpClient {
             $(#[$attr])*
             pub async fn $name(&self, body: $req_body, $($v: $t),*) -> Result<$ret_type, $crate::wxcorp::WxcorpApiError> {
                 self.call_post(
-                    $url,
+                    $path,
                     // stringify! 将 ident 转为字符串形式
                     &[$((stringify!($v), $v)),*],
                     &body,
@@ -1,10 +1,22 @@
 //! 企业微信相关 api 封装
 //!
-//! ```
+//! ```ignore
 //! let client = WxcorpClient::new();
 //! let res_data = client.request_user_id_by_auth_code(access_token, code)
 //! ```
 //!
+//! 设置 token 管理器后，按应用名称自动获取、缓存并刷新 access token：
+//! ```ignore
+//! let manager = WxcorpTokenManager::new().with_agent("app", corpid, app_secret);
+//! let client = WxcorpClient::builder().token_manager(manager).agent("app").build();
+//! let res_data = client.request_user_id_by_auth_code(None, code)
+//! ```
+//!
+//! 可以通过 builder 自定义请求地址，比如集成测试时指向本地的 mock 服务：
+//! ```ignore
+//! let client = WxcorpClient::builder().base_url("http://127.0.0.1:8080").build();
+//! ```
+//!
 
 mod api;
 pub use api::*;
@@ -12,3 +24,7 @@ pub use api::*;
 mod client;
 pub use client::WxcorpApiError;
 pub use client::WxcorpClient;
+pub use client::WXCORP_DEFAULT_BASE_URL;
+
+mod token;
+pub use token::WxcorpTokenManager;
@@ -0,0 +1,150 @@
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::sync::Mutex;
+
+use super::{WxcorpApiError, WxcorpClient};
+use crate::common::AccessTokenData;
+use crate::token_store::{get_or_fetch_token, MemoryTokenStore, TokenStore};
+
+/// 企业微信的 access token 管理器
+///
+/// 企业微信中每个自建应用、通讯录同步、客户联系都有各自的 secret 和 access token，
+/// 管理器按 (corpid, secret) 分别缓存 access token 到过期前 5 分钟，并合并并发的刷新请求。
+///
+/// ```ignore
+/// let manager = WxcorpTokenManager::new()
+///     .with_agent("app", corpid, app_secret)
+///     .with_agent("contacts", corpid, contacts_secret);
+/// let client = WxcorpClient::builder().token_manager(manager).agent("app").build();
+/// // 无需再传入 access token
+/// let res_data = client.request_send(&body, None).await;
+/// let res_data = client.with_agent("contacts").request_external_contact_list(None, Some(userid)).await;
+/// ```
+pub struct WxcorpTokenManager {
+    store: Arc<dyn TokenStore>,
+    /// 应用名称 -> (corpid, secret)
+    agents: HashMap<String, (String, String)>,
+    /// 每个 (corpid, secret) 一把进程内的刷新锁
+    locks: std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl Default for WxcorpTokenManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WxcorpTokenManager {
+    pub fn new() -> Self {
+        Self {
+            store: Arc::new(MemoryTokenStore::new()),
+            agents: HashMap::new(),
+            locks: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 使用指定的存储缓存 access token
+    pub fn with_store(mut self, store: Arc<dyn TokenStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// 注册应用，之后可以通过名称获取该应用的 access token
+    pub fn with_agent(
+        mut self,
+        agent: impl Into<String>,
+        corpid: impl Into<String>,
+        secret: impl Into<String>,
+    ) -> Self {
+        self.agents
+            .insert(agent.into(), (corpid.into(), secret.into()));
+        self
+    }
+
+    /// 获取 (corpid, secret) 对应的 access token，缓存不可用时会重新获取
+    pub async fn access_token(
+        &self,
+        client: &WxcorpClient,
+        corpid: &str,
+        secret: &str,
+    ) -> Result<String, WxcorpApiError> {
+        self.get_or_fetch(client, corpid, secret, None).await
+    }
+
+    /// 强制刷新 access token。`stale` 为调用方确认已失效的 token，如果缓存中的 token 已经被其他调用刷新过，则直接返回缓存
+    pub async fn refresh_access_token(
+        &self,
+        client: &WxcorpClient,
+        corpid: &str,
+        secret: &str,
+        stale: &str,
+    ) -> Result<String, WxcorpApiError> {
+        self.get_or_fetch(client, corpid, secret, Some(stale)).await
+    }
+
+    /// 获取已注册应用的 access token
+    pub async fn agent_access_token(
+        &self,
+        client: &WxcorpClient,
+        agent: &str,
+    ) -> Result<String, WxcorpApiError> {
+        let (corpid, secret) = self.agent(agent)?;
+        self.get_or_fetch(client, corpid, secret, None).await
+    }
+
+    /// 强制刷新已注册应用的 access token
+    pub async fn refresh_agent_access_token(
+        &self,
+        client: &WxcorpClient,
+        agent: &str,
+        stale: &str,
+    ) -> Result<String, WxcorpApiError> {
+        let (corpid, secret) = self.agent(agent)?;
+        self.get_or_fetch(client, corpid, secret, Some(stale)).await
+    }
+
+    fn agent(&self, agent: &str) -> Result<(&str, &str), WxcorpApiError> {
+        self.agents
+            .get(agent)
+            .map(|(corpid, secret)| (corpid.as_str(), secret.as_str()))
+            .ok_or_else(|| WxcorpApiError::UnknownAgent(agent.into()))
+    }
+
+    async fn get_or_fetch(
+        &self,
+        client: &WxcorpClient,
+        corpid: &str,
+        secret: &str,
+        stale: Option<&str>,
+    ) -> Result<String, WxcorpApiError> {
+        // 不直接把 secret 写入存储的 key 中
+        let key = format!("wxcorp:access_token:{}:{:016x}", corpid, fnv1a64(secret));
+        let lock = self
+            .locks
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        get_or_fetch_token(self.store.as_ref(), &lock, &key, stale, || async {
+            // 这里不能走 request_access_token，否则会和 client 内部获取 token 的逻辑形成递归
+            let data = client
+                .get_value(
+                    "/cgi-bin/gettoken",
+                    &[("corpid", Some(corpid)), ("corpsecret", Some(secret))],
+                    None,
+                )
+                .await?;
+            let data: AccessTokenData = serde_json::from_value(data)?;
+            Ok((data.access_token, data.expires_in))
+        })
+        .await
+    }
+}
+
+/// 跨进程、跨版本稳定的哈希，用于生成存储的 key
+fn fnv1a64(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -6,7 +6,7 @@ use crate::{common::AccessTokenData, wxmini_api_get, wxmini_api_post, wxmini_api
 wxmini_api_get!(
     /// [获取 access token](https://developers.weixin.qq.com/miniprogram/dev/OpenApiDoc/mp-access-token/getAccessToken.html)
     request_access_token,
-    "api.weixin.qq.com/cgi-bin/token",
+    "/cgi-bin/token",
     (appid: Option<&str>, secret: Option<&str>, grant_type: Option<&str>),
     AccessTokenData
 );
@@ -22,9 +22,7 @@ pub struct StableAccessTokenRequestBody {
 wxmini_api_post!(
     /// [获取稳定版接口调用凭据](https://developers.weixin.qq.com/miniprogram/dev/OpenApiDoc/mp-access-token/getStableAccessToken.html)
     request_stable_access_token,
-    "api.weixin.qq.com/cgi-bin/stable_token
-
-",
+    "/cgi-bin/stable_token",
     (),
     &StableAccessTokenRequestBody,
     AccessTokenData
@@ -58,7 +56,7 @@ pub struct BatchdownloadfileResponseData {
 wxmini_api_post!(
     /// [获取文件下载链接](https://developers.weixin.qq.com/miniprogram/dev/wxcloudrun/src/development/storage/service/download.html)
     request_batch_download_file,
-    "api.weixin.qq.com/tcb/batchdownloadfile",
+    "/tcb/batchdownloadfile",
     (access_token: Option<&str>),
     &BatchdownloadfileRequestBody,
     BatchdownloadfileResponseData
@@ -88,7 +86,7 @@ pub struct MediaSecCheckResponseData {
 wxmini_api_post!(
     /// [音视频内容安全识别](https://developers.weixin.qq.com/miniprogram/dev/OpenApiDoc/sec-center/sec-check/mediaCheckAsync.html)
     request_media_sec_check,
-    "api.weixin.qq.com/wxa/media_check_async",
+    "/wxa/media_check_async",
     (access_token: Option<&str>),
     &MediaSecCheckBody,
     MediaSecCheckResponseData
@@ -135,7 +133,7 @@ pub struct MsgSecCheckResponseData {
 wxmini_api_post!(
     /// [文本内容安全识别](https://developers.weixin.qq.com/miniprogram/dev/OpenApiDoc/sec-center/sec-check/msgSecCheck.html)
     request_msg_sec_check,
-    "api.weixin.qq.com/wxa/msg_sec_check",
+    "/wxa/msg_sec_check",
     (access_token: Option<&str>),
     &MsgSecCheckBody,
     MsgSecCheckResponseData
@@ -157,7 +155,7 @@ pub struct SubscribeMessageResponseData {}
 wxmini_api_post!(
   /// [发送订阅消息](https://developers.weixin.qq.com/miniprogram/dev/OpenApiDoc/mp-message-management/subscribe-message/sendMessage.html)
   request_subscribe_message,
-  "api.weixin.qq.com/cgi-bin/message/subscribe/send",
+  "/cgi-bin/message/subscribe/send",
   (access_token: Option<&str>),
   &SubscribeMessageRequestBody,
   SubscribeMessageResponseData
@@ -181,7 +179,7 @@ pub struct UploadfileResponseData {
 wxmini_api_post!(
   /// [获取文件上传链接](https://developers.weixin.qq.com/miniprogram/dev/wxcloudrun/src/development/storage/service/upload.html)
   request_uploadfile,
-  "api.weixin.qq.com/tcb/uploadfile",
+  "/tcb/uploadfile",
   (access_token: Option<&str>),
   &UploadfileRequestBody,
   UploadfileResponseData
@@ -199,7 +197,7 @@ pub struct BatchdeletefileResponseData {}
 wxmini_api_post!(
   /// [删除文件](https://developers.weixin.qq.com/miniprogram/dev/wxcloudrun/src/development/storage/service/delete.html)
   request_batchdeletefile,
-  "api.weixin.qq.com/tcb/batchdeletefile",
+  "/tcb/batchdeletefile",
   (access_token: Option<&str>),
   &BatchdeletefileRequestBody,
   BatchdeletefileResponseData
@@ -225,7 +223,7 @@ pub struct GetwxacodeUnlimitesponseData {
 wxmini_api_post!(
     /// [获取不限制的小程序码](https://developers.weixin.qq.com/miniprogram/dev/OpenApiDoc/qrcode-link/qr-code/getUnlimitedQRCode.html)
     request_getwxacodeunlimit,
-    "api.weixin.qq.com/wxa/getwxacodeunlimit",
+    "/wxa/getwxacodeunlimit",
     (access_token: Option<&str>),
     &GetwxacodeUnlimitRequestBody,
     GetwxacodeUnlimitesponseData
@@ -245,7 +243,7 @@ pub struct CreatewxaqrcodeResponseData {
 wxmini_api_post!(
     /// [获取小程序二维码](https://developers.weixin.qq.com/miniprogram/dev/OpenApiDoc/qrcode-link/qr-code/createQRCode.html)
     request_createwxaqrcode,
-    "api.weixin.qq.com/cgi-bin/wxaapp/createwxaqrcode",
+    "/cgi-bin/wxaapp/createwxaqrcode",
     (access_token: Option<&str>),
     &CreatewxaqrcodeRequestBody,
     CreatewxaqrcodeResponseData
@@ -278,7 +276,7 @@ pub struct QrcodeCreateResponseData {
 wxmini_api_post!(
     /// [生成带参数的二维码](https://developers.weixin.qq.com/doc/offiaccount/Account_Management/Generating_a_Parametric_QR_Code.html)
     request_qrcode_create,
-    "api.weixin.qq.com/cgi-bin/qrcode/create",
+    "/cgi-bin/qrcode/create",
     (access_token: Option<&str>),
     &QrcodeCreateRequestBody,
     QrcodeCreateResponseData
@@ -291,7 +289,7 @@ pub struct MenuCreateRequestBody {
 wxmini_api_post!(
     /// [自定义菜单-创建接口](https://developers.weixin.qq.com/doc/offiaccount/Custom_Menus/Creating_Custom-Defined_Menu.html)
     request_menu_create,
-    "api.weixin.qq.com/cgi-bin/menu/create",
+    "/cgi-bin/menu/create",
     (access_token: Option<&str>),
     &MenuCreateRequestBody,
     Value
@@ -318,7 +316,7 @@ pub struct TagsCreateResponseData {
 wxmini_api_post!(
     /// [用户标签管理-创建标签](https://developers.weixin.qq.com/doc/offiaccount/User_Management/User_Tag_Management.html)
     request_tags_create,
-    "api.weixin.qq.com/cgi-bin/tags/create",
+    "/cgi-bin/tags/create",
     (access_token: Option<&str>),
     &TagsCreateRequestBody,
     TagsCreateResponseData
@@ -336,7 +334,7 @@ pub struct TagsGetResponseData {
 wxmini_api_get!(
     /// [用户标签管理-获取公众号已创建的标签](https://developers.weixin.qq.com/doc/offiaccount/User_Management/User_Tag_Management.html)
     request_tags_get,
-    "api.weixin.qq.com/cgi-bin/tags/get",
+    "/cgi-bin/tags/get",
     (access_token: Option<&str>),
     TagsGetResponseData
 );
@@ -352,7 +350,7 @@ pub struct TagsMembersBatchtaggingResponseData {}
 wxmini_api_post!(
     /// [用户标签管理-批量为用户打标签](https://developers.weixin.qq.com/doc/offiaccount/User_Management/User_Tag_Management.html)
     request_tags_members_batchtagging,
-    "api.weixin.qq.com/cgi-bin/tags/members/batchtagging",
+    "/cgi-bin/tags/members/batchtagging",
     (access_token: Option<&str>),
     &TagsMembersBatchtaggingRequestBody,
     Value
@@ -372,7 +370,7 @@ pub struct UserGetResponseData {
 wxmini_api_get!(
     /// [获取用户列表](https://developers.weixin.qq.com/doc/offiaccount/User_Management/Getting_a_User_List.html)
     request_user_get,
-    "api.weixin.qq.com/cgi-bin/user/get",
+    "/cgi-bin/user/get",
     (access_token: Option<&str>, next_openid: Option<&str>),
     UserGetResponseData
 );
@@ -385,7 +383,7 @@ pub struct GetTicketResponseData {
 wxmini_api_get!(
     /// [jssdk签名](https://developers.weixin.qq.com/doc/offiaccount/OA_Web_Apps/JS-SDK.html#62)
     request_getticket,
-    "api.weixin.qq.com/cgi-bin/ticket/getticket?type=jsapi",
+    "/cgi-bin/ticket/getticket?type=jsapi",
     // TODO: 待使用 bon.rs 改造，支持每个接口 builder 形式传入下述参数，from_appid 是用于云托管资源复用场景区别以哪个调用方身份的
     (access_token: Option<&str>, from_appid: Option<&str>),
     GetTicketResponseData
@@ -410,7 +408,7 @@ pub struct MessageTemplateSendResponseData {}
 wxmini_api_post!(
     /// [发送模板消息](https://developers.weixin.qq.com/doc/offiaccount/Message_Management/Template_Message_Interface.html#%E5%8F%91%E9%80%81%E6%A8%A1%E6%9D%BF%E6%B6%88%E6%81%AF)
     request_message_template_send,
-    "api.weixin.qq.com/cgi-bin/message/template/send",
+    "/cgi-bin/message/template/send",
     (access_token: Option<&str>),
     &MessageTemplateSendRequestBody,
     MessageTemplateSendResponseData
@@ -448,7 +446,7 @@ pub struct UserInfoBatchgetResponseData {
 wxmini_api_post!(
     /// [批量获取用户基本信息](https://developers.weixin.qq.com/doc/offiaccount/User_Management/Get_users_basic_information_UnionID.html#UinonId)
     request_user_info_batchget,
-    "api.weixin.qq.com/cgi-bin/user/info/batchget",
+    "/cgi-bin/user/info/batchget",
     (access_token: Option<&str>),
     &UserInfoBatchgetRequestBody,
     UserInfoBatchgetResponseData
@@ -475,7 +473,7 @@ pub struct GenerateSchemeResponseData {
 wxmini_api_post!(
     /// [获取加密scheme码](https://developers.weixin.qq.com/miniprogram/dev/OpenApiDoc/qrcode-link/url-scheme/generateScheme.html)
     request_generatescheme,
-    "api.weixin.qq.com/wxa/generatescheme",
+    "/wxa/generatescheme",
     (access_token: Option<&str>),
     &GenerateSchemeRequestBody,
     GenerateSchemeResponseData
@@ -495,7 +493,7 @@ pub struct GenerateShortLinkResponseData {
 wxmini_api_post!(
     /// [获取ShortLink](https://developers.weixin.qq.com/miniprogram/dev/OpenApiDoc/qrcode-link/short-link/generateShortLink.html)
     request_genwxashortlink,
-    "api.weixin.qq.com/wxa/genwxashortlink",
+    "/wxa/genwxashortlink",
     (access_token: Option<&str>),
     &GenerateShortLinkRequestBody,
     GenerateShortLinkResponseData
@@ -529,7 +527,7 @@ pub struct ScanQrcodeResponseData {
 wxmini_api_post_form!(
     /// [条形码识别](https://developers.weixin.qq.com/miniprogram/dev/OpenApiDoc/img-ocr/img/scanQRCode.html) 注意：并不支持识别小程序码
     request_scan_qrcode,
-    "api.weixin.qq.com/cv/img/qrcode",
+    "/cv/img/qrcode",
     (access_token: Option<&str>),
     &ScanQrcodeRequestBody,
     ScanQrcodeResponseData
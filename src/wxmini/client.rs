@@ -1,11 +1,14 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
 use serde::Serialize;
 use serde_json::{json, Value};
-use tracing::error;
 
 use thiserror::Error;
 
+use super::token::WxminiTokenManager;
+use crate::common::{default_http_client, with_access_token};
+use crate::token_store::TokenStoreError;
+
 #[derive(Error, Debug)]
 pub enum WxminiApiError {
     /// 微信 api 返回的错误码不为成功
@@ -23,39 +26,136 @@ pub enum WxminiApiError {
     /// 请求微信 api 网络出错
     #[error("request error: {0}")]
     RequestErr(#[from] reqwest::Error),
+
+    /// 读写 access token 缓存出错
+    #[error("{0}")]
+    TokenStoreErr(#[from] TokenStoreError),
 }
 
+/// 微信小程序/公众号 api 的默认请求地址
+pub const WXMINI_DEFAULT_BASE_URL: &str = "https://api.weixin.qq.com";
+
+#[derive(bon::Builder)]
 pub struct WxminiClient {
-    is_http: bool,
+    /// 请求的协议和域名，不以 `/` 结尾，默认为 [`WXMINI_DEFAULT_BASE_URL`]
+    #[builder(into, default = WXMINI_DEFAULT_BASE_URL.to_string())]
+    base_url: String,
+    /// 发起请求所用的 http client，所有 api 调用都会复用它（以及它的连接池），
+    /// 可以通过 [`HttpClientConfig`](crate::common::HttpClientConfig) 构造带超时、代理等配置的 client。默认使用进程内共享的 client
+    #[builder(default = default_http_client())]
+    http_client: reqwest::Client,
+    /// 设置后，调用需要 access token 的接口时如果未传入 access token，会自动从管理器中获取，
+    /// 并在 access token 失效时强制刷新后重试一次
+    #[builder(into)]
+    token_manager: Option<Arc<WxminiTokenManager>>,
+}
+
+impl Default for WxminiClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl WxminiClient {
     pub fn new() -> Self {
-        Self { is_http: false }
+        Self::builder().build()
     }
     pub fn without_https() -> Self {
-        Self { is_http: true }
+        Self::builder()
+            .base_url(WXMINI_DEFAULT_BASE_URL.replacen("https://", "http://", 1))
+            .build()
     }
 }
 
 impl WxminiClient {
     pub(crate) async fn call_get<D, F>(
         &self,
-        endpoint_without_protocol: &str,
+        path: &str,
         query: &[(&str, Option<&str>)],
         map: F,
     ) -> Result<D, WxminiApiError>
     where
         F: FnOnce(Value) -> Result<D, serde_json::Error>,
     {
-        let client = reqwest::Client::new();
-        let response = client
-            .get(&format!(
-                "{}://{}",
-                if self.is_http { "http" } else { "https" },
-                endpoint_without_protocol
-            ))
-            .query(query)
+        let token = self.managed_access_token(query).await?;
+        let data = match (self.get_value(path, query, token.as_deref()).await, token) {
+            (Err(err), Some(token)) if is_access_token_invalid(&err) => {
+                let token = self.refresh_managed_access_token(&token).await?;
+                self.get_value(path, query, Some(&token)).await?
+            }
+            (result, _) => result?,
+        };
+
+        match map(data) {
+            Ok(data) => Ok(data),
+            Err(err) => Err(WxminiApiError::WxminiResDeserializeErr(err)),
+        }
+    }
+
+    pub(crate) async fn call_post<D, B, F>(
+        &self,
+        path: &str,
+        query: &[(&str, Option<&str>)],
+        body: &B,
+        map: F,
+        is_form: bool,
+    ) -> Result<D, WxminiApiError>
+    where
+        B: Serialize + Debug,
+        F: FnOnce(Value) -> Result<D, serde_json::Error>,
+    {
+        let token = self.managed_access_token(query).await?;
+        let (data, is_buffer) = match (
+            self.post_value(path, query, body, is_form, token.as_deref())
+                .await,
+            token,
+        ) {
+            (Err(err), Some(token)) if is_access_token_invalid(&err) => {
+                let token = self.refresh_managed_access_token(&token).await?;
+                self.post_value(path, query, body, is_form, Some(&token))
+                    .await?
+            }
+            (result, _) => result?,
+        };
+
+        match map(data) {
+            Ok(data) => Ok(data),
+            Err(_) if is_buffer => Err(WxminiApiError::WxminiResBufferErr),
+            Err(err) => Err(WxminiApiError::WxminiResDeserializeErr(err)),
+        }
+    }
+
+    /// 接口需要 access token 但调用方未传入时，从 token 管理器中获取
+    async fn managed_access_token(
+        &self,
+        query: &[(&str, Option<&str>)],
+    ) -> Result<Option<String>, WxminiApiError> {
+        match &self.token_manager {
+            Some(manager) if query.contains(&("access_token", None)) => {
+                Ok(Some(manager.access_token(self).await?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn refresh_managed_access_token(&self, stale: &str) -> Result<String, WxminiApiError> {
+        let manager = self
+            .token_manager
+            .as_ref()
+            .expect("managed access token should come from token manager");
+        manager.refresh_access_token(self, stale).await
+    }
+
+    pub(crate) async fn get_value(
+        &self,
+        path: &str,
+        query: &[(&str, Option<&str>)],
+        access_token: Option<&str>,
+    ) -> Result<Value, WxminiApiError> {
+        let response = self
+            .http_client
+            .get(format!("{}{}", self.base_url, path))
+            .query(&with_access_token(query, access_token))
             .send()
             .await?;
         let data: Value = response.json().await?;
@@ -69,32 +169,25 @@ impl WxminiClient {
             return Err(WxminiApiError::ApiCodeNotOk(data));
         }
 
-        match map(data) {
-            Ok(data) => Ok(data),
-            Err(err) => Err(WxminiApiError::WxminiResDeserializeErr(err)),
-        }
+        Ok(data)
     }
 
-    pub(crate) async fn call_post<D, B, F>(
+    /// 返回响应内容，以及响应是否为 buffer
+    pub(crate) async fn post_value<B>(
         &self,
-        endpoint_without_protocol: &str,
+        path: &str,
         query: &[(&str, Option<&str>)],
         body: &B,
-        map: F,
         is_form: bool,
-    ) -> Result<D, WxminiApiError>
+        access_token: Option<&str>,
+    ) -> Result<(Value, bool), WxminiApiError>
     where
         B: Serialize + Debug,
-        F: FnOnce(Value) -> Result<D, serde_json::Error>,
     {
-        let client = reqwest::Client::new();
-        let mut response = client
-            .post(&format!(
-                "{}://{}",
-                if self.is_http { "http" } else { "https" },
-                endpoint_without_protocol
-            ))
-            .query(query);
+        let mut response = self
+            .http_client
+            .post(format!("{}{}", self.base_url, path))
+            .query(&with_access_token(query, access_token));
         if is_form {
             response = response.form(body);
         } else {
@@ -122,10 +215,7 @@ impl WxminiClient {
                 return Err(WxminiApiError::ApiCodeNotOk(data));
             }
 
-            match map(data) {
-                Ok(data) => Ok(data),
-                Err(err) => Err(WxminiApiError::WxminiResDeserializeErr(err)),
-            }
+            Ok((data, false))
         } else {
             let buffer = response.bytes().await?.to_vec();
             let value = json!(
@@ -133,23 +223,33 @@ impl WxminiClient {
                     "buffer": buffer
                 }
             );
-            match map(value) {
-                Ok(data) => Ok(data),
-                Err(_) => Err(WxminiApiError::WxminiResBufferErr),
-            }
+            Ok((value, true))
         }
     }
 }
 
+/// access token 失效或过期的错误码
+const INVALID_ACCESS_TOKEN_ERRCODES: [i64; 3] = [40001, 42001, 40014];
+
+pub(crate) fn is_access_token_invalid(err: &WxminiApiError) -> bool {
+    match err {
+        WxminiApiError::ApiCodeNotOk(data) => data
+            .get("errcode")
+            .and_then(|v| v.as_i64())
+            .is_some_and(|code| INVALID_ACCESS_TOKEN_ERRCODES.contains(&code)),
+        _ => false,
+    }
+}
+
 #[macro_export]
 macro_rules! wxmini_api_get {
-    ($(#[$attr:meta])* $name: ident, $endpoint_without_protocol: tt, ($($v:ident: $t:ty),*), $ret_type: ty) => {
+    ($(#[$attr:meta])* $name: ident, $path: tt, ($($v:ident: $t:ty),*), $ret_type: ty) => {
         impl $crate::wxmini::WxminiClient {
             $(#[$attr])*
             pub async fn $name(&self, $($v: $t),*) -> Result<$ret_type, $crate::wxmini::WxminiApiError> {
                 self.call_get(
                     &format!(
-                        $endpoint_without_protocol
+                        $path
                     ),
                     // stringify! 将 ident 转为字符串形式
                     &[$((stringify!($v), $v)),*],
@@ -163,13 +263,13 @@ macro_rules! wxmini_api_get {
 
 #[macro_export]
 macro_rules! wxmini_api_post_inner {
-    ($(#[$attr:meta])* $name: ident, $endpoint_without_protocol: tt, ($($v:ident: $t:ty),*), $req_body:ty, $ret_type:ty, $is_form:expr) => {
+    ($(#[$attr:meta])* $name: ident, $path: tt, ($($v:ident: $t:ty),*), $req_body:ty, $ret_type:ty, $is_form:expr) => {
         impl $crate::wxmini::WxminiClient {
             $(#[$attr])*
             pub async fn $name(&self, body: $req_body, $($v: $t),*) -> Result<$ret_type, $crate::wxmini::WxminiApiError> {
                 self.call_post(
                     &format!(
-                        $endpoint_without_protocol
+                        $path
                     ),
                     // stringify! 将 ident 转为字符串形式
                     &[$((stringify!($v), $v)),*],
@@ -185,14 +285,14 @@ macro_rules! wxmini_api_post_inner {
 
 #[macro_export]
 macro_rules! wxmini_api_post {
-    ($(#[$attr:meta])* $name: ident, $endpoint_without_protocol: tt, ($($v:ident: $t:ty),*), $req_body:ty, $ret_type:ty) => {
-        $crate::wxmini_api_post_inner!($name, $endpoint_without_protocol, ($($v: $t),*), $req_body, $ret_type, false);
+    ($(#[$attr:meta])* $name: ident, $path: tt, ($($v:ident: $t:ty),*), $req_body:ty, $ret_type:ty) => {
+        $crate::wxmini_api_post_inner!($(#[$attr])* $name, $path, ($($v: $t),*), $req_body, $ret_type, false);
     };
 }
 
 #[macro_export]
 macro_rules! wxmini_api_post_form {
-    ($(#[$attr:meta])* $name: ident, $endpoint_without_protocol: tt, ($($v:ident: $t:ty),*), $req_body:ty, $ret_type:ty) => {
-        $crate::wxmini_api_post_inner!($name, $endpoint_without_protocol, ($($v: $t),*), $req_body, $ret_type, true);
+    ($(#[$attr:meta])* $name: ident, $path: tt, ($($v:ident: $t:ty),*), $req_body:ty, $ret_type:ty) => {
+        $crate::wxmini_api_post_inner!($(#[$attr])* $name, $path, ($($v: $t),*), $req_body, $ret_type, true);
     };
 }
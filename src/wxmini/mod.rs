@@ -3,7 +3,7 @@
 //! ## 使用场景
 //! ### 在微信云托管环境
 //! 此时无需提供 access token，且 api 需要使用 http 协议进行调用：
-//! ```
+//! ```ignore
 //! let client = WxminiClient::without_https();
 //! let res_data = client.request_msg_sec_check(&body, None).await;
 //! ```
@@ -11,11 +11,26 @@
 //!
 //! ### 在非云托管环境
 //! 比如在自己的服务器内请求微信 api，这种场景需要提供 access token，且需要使用 https 协议：
-//! ```
+//! ```ignore
 //! let client = WxminiClient::new();
 //! let res_data = client.request_msg_sec_check(&body, Some(access_token)).await;
 //! ```
 //!
+//! ### 自动管理 access token
+//! 设置 token 管理器后，未传入 access token 时会自动获取、缓存并在失效时刷新：
+//! ```ignore
+//! let client = WxminiClient::builder()
+//!     .token_manager(WxminiTokenManager::new(appid, secret))
+//!     .build();
+//! let res_data = client.request_msg_sec_check(&body, None).await;
+//! ```
+//!
+//! ### 自定义请求地址
+//! 比如集成测试时指向本地的 mock 服务：
+//! ```ignore
+//! let client = WxminiClient::builder().base_url("http://127.0.0.1:8080").build();
+//! ```
+//!
 
 mod api;
 pub use api::*;
@@ -23,3 +38,7 @@ pub use api::*;
 mod client;
 pub use client::WxminiApiError;
 pub use client::WxminiClient;
+pub use client::WXMINI_DEFAULT_BASE_URL;
+
+mod token;
+pub use token::WxminiTokenManager;
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use super::client::is_access_token_invalid;
+use super::{GetTicketResponseData, StableAccessTokenRequestBody, WxminiApiError, WxminiClient};
+use crate::common::AccessTokenData;
+use crate::token_store::{get_or_fetch_token, MemoryTokenStore, TokenStore};
+
+/// 小程序/公众号的 access token 管理器
+///
+/// 使用[稳定版接口](https://developers.weixin.qq.com/miniprogram/dev/OpenApiDoc/mp-access-token/getStableAccessToken.html)按需获取 access token，
+/// 并缓存到过期前 5 分钟。多个请求同时发现缓存失效时，只会发出一次获取请求。
+///
+/// 默认缓存在进程内存中，多个服务实例共用同一个 appid 时，可以通过 [`WxminiTokenManager::with_store`] 使用共享的存储。
+///
+/// ```ignore
+/// let client = WxminiClient::builder()
+///     .token_manager(WxminiTokenManager::new("appid", "secret"))
+///     .build();
+/// // 无需再传入 access token
+/// let res_data = client.request_msg_sec_check(&body, None).await;
+/// ```
+pub struct WxminiTokenManager {
+    appid: String,
+    secret: String,
+    store: Arc<dyn TokenStore>,
+    access_token_lock: Mutex<()>,
+    jsapi_ticket_lock: Mutex<()>,
+}
+
+impl WxminiTokenManager {
+    pub fn new(appid: impl Into<String>, secret: impl Into<String>) -> Self {
+        Self {
+            appid: appid.into(),
+            secret: secret.into(),
+            store: Arc::new(MemoryTokenStore::new()),
+            access_token_lock: Mutex::new(()),
+            jsapi_ticket_lock: Mutex::new(()),
+        }
+    }
+
+    /// 使用指定的存储缓存 access token 和 jsapi ticket
+    pub fn with_store(mut self, store: Arc<dyn TokenStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    pub fn appid(&self) -> &str {
+        &self.appid
+    }
+
+    fn access_token_key(&self) -> String {
+        format!("wxmini:access_token:{}", self.appid)
+    }
+
+    fn jsapi_ticket_key(&self) -> String {
+        format!("wxmini:jsapi_ticket:{}", self.appid)
+    }
+
+    /// 获取 access token，缓存不可用时会重新获取
+    pub async fn access_token(&self, client: &WxminiClient) -> Result<String, WxminiApiError> {
+        self.get_or_fetch_access_token(client, None).await
+    }
+
+    /// 强制刷新 access token。`stale` 为调用方确认已失效的 token，如果缓存中的 token 已经被其他调用刷新过，则直接返回缓存
+    pub async fn refresh_access_token(
+        &self,
+        client: &WxminiClient,
+        stale: &str,
+    ) -> Result<String, WxminiApiError> {
+        self.get_or_fetch_access_token(client, Some(stale)).await
+    }
+
+    async fn get_or_fetch_access_token(
+        &self,
+        client: &WxminiClient,
+        stale: Option<&str>,
+    ) -> Result<String, WxminiApiError> {
+        let key = self.access_token_key();
+        get_or_fetch_token(
+            self.store.as_ref(),
+            &self.access_token_lock,
+            &key,
+            stale,
+            || async {
+                let body = StableAccessTokenRequestBody {
+                    grant_type: "client_credential".into(),
+                    appid: self.appid.clone(),
+                    secret: self.secret.clone(),
+                    force_refresh: Some(stale.is_some()),
+                };
+                // 这里不能走 request_stable_access_token，否则会和 client 内部获取 token 的逻辑形成递归
+                let (data, _) = client
+                    .post_value("/cgi-bin/stable_token", &[], &body, false, None)
+                    .await?;
+                let data: AccessTokenData = serde_json::from_value(data)?;
+                Ok((data.access_token, data.expires_in))
+            },
+        )
+        .await
+    }
+
+    /// 获取[公众号 jsapi ticket](https://developers.weixin.qq.com/doc/offiaccount/OA_Web_Apps/JS-SDK.html#62)，和 access token 一样会被缓存
+    pub async fn jsapi_ticket(&self, client: &WxminiClient) -> Result<String, WxminiApiError> {
+        let key = self.jsapi_ticket_key();
+        get_or_fetch_token(
+            self.store.as_ref(),
+            &self.jsapi_ticket_lock,
+            &key,
+            None,
+            || async {
+                let path = "/cgi-bin/ticket/getticket?type=jsapi";
+                let token = self.access_token(client).await?;
+                let data = match client
+                    .get_value(path, &[("access_token", Some(&token))], None)
+                    .await
+                {
+                    Err(err) if is_access_token_invalid(&err) => {
+                        let token = self.refresh_access_token(client, &token).await?;
+                        client
+                            .get_value(path, &[("access_token", Some(&token))], None)
+                            .await?
+                    }
+                    result => result?,
+                };
+                let data: GetTicketResponseData = serde_json::from_value(data)?;
+                Ok((data.ticket, data.expires_in as u64))
+            },
+        )
+        .await
+    }
+}
@@ -1,11 +1,39 @@
-use reqwest::Client;
+use chrono::{DateTime, FixedOffset};
+use reqwest::Method;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use super::error::{WxPayFailedResponse, WxpayApiError};
-use super::utils::generate_wxpay_request_signature;
+use super::client::WxpayClient;
+use super::error::WxpayApiError;
+use super::sensitive::{SensitiveField, SensitiveFields};
+use super::utils::{
+    parse_public_key, sign_wxpay_app_pay, sign_wxpay_jsapi_pay, WxpayAppPayParams,
+    WxpayJsapiPayParams,
+};
 
-#[derive(Debug, Deserialize, Serialize)]
+/// 微信支付 api 的默认请求地址
+pub const WXPAY_DEFAULT_BASE_URL: &str = "https://api.mch.weixin.qq.com";
+
+/// 下面的独立请求函数每次调用都需要解析商户私钥，多次调用时建议直接使用 [`WxpayClient`]
+pub(crate) fn temporary_client(
+    mchid: &str,
+    mch_private_key: &str,
+    mch_serial_no: &str,
+    base_url: &str,
+    http_client: Option<&reqwest::Client>,
+) -> Result<WxpayClient, WxpayApiError> {
+    WxpayClient::builder()
+        .mchid(mchid)
+        .mch_private_key(mch_private_key)
+        .mch_serial_no(mch_serial_no)
+        .base_url(base_url)
+        .maybe_http_client(http_client.cloned())
+        // 没有配置平台证书，不验证响应签名，需要验签时请使用 WxpayClient
+        .verify_response(false)
+        .build()
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BatchTransferRequestBody {
     pub appid: String,
     /// 只能是数字和字母的组合
@@ -19,21 +47,143 @@ pub struct BatchTransferRequestBody {
     pub notify_url: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl SensitiveFields for BatchTransferRequestBody {
+    fn sensitive_fields(&mut self) -> Vec<&mut SensitiveField> {
+        self.transfer_detail_list
+            .iter_mut()
+            .filter_map(|detail| detail.user_name.as_mut())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TransferDetail {
     pub out_detail_no: String,
     pub transfer_amount: u64,
     pub transfer_remark: String,
     pub openid: String,
-    pub user_name: Option<String>,
+    /// 收款用户姓名，需要加密
+    pub user_name: Option<SensitiveField>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct BatchTransferResponse {
-    pub out_detail_no: String,
+    pub out_batch_no: String,
+    /// 微信批次单号
     pub batch_id: String,
     pub create_time: String,
-    pub out_batch_no: String,
+    pub batch_status: Option<BatchStatus>,
+}
+
+/// 转账批次状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BatchStatus {
+    /// 待付款确认，需要付款出资商户在商家助手小程序或服务商助手小程序进行付款确认
+    WaitPay,
+    /// 已受理，批次受理成功 30 分钟后仍处于该状态，可能是商户账户余额不足等原因
+    Accepted,
+    /// 转账中，已开始处理批次内的转账明细单
+    Processing,
+    /// 已完成，批次内的所有转账明细单都已处理完成
+    Finished,
+    /// 已关闭
+    Closed,
+    /// 微信支付新增的未知状态
+    #[serde(other)]
+    Unknown,
+}
+
+/// 转账批次关闭原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BatchCloseReason {
+    /// 商户主动撤销
+    MerchantRevocation,
+    /// 超时关闭
+    OverdueClose,
+    #[serde(other)]
+    Unknown,
+}
+
+/// 转账明细状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransferDetailStatus {
+    /// 初始态，系统转账校验中
+    Init,
+    /// 待商户确认，符合免密条件时会自动流转为转账中
+    WaitPay,
+    /// 转账中
+    Processing,
+    /// 转账成功
+    Success,
+    /// 转账失败
+    Fail,
+    #[serde(other)]
+    Unknown,
+}
+
+/// [转账明细失败原因](https://pay.weixin.qq.com/doc/v3/merchant/4012458865)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransferFailReason {
+    /// 账户冻结
+    AccountFrozen,
+    /// 用户未实名
+    RealNameCheckFail,
+    /// 用户姓名校验失败
+    NameNotCorrect,
+    /// Openid校验失败
+    OpenidInvalid,
+    /// 超过用户单笔收款额度
+    TransferQuotaExceed,
+    /// 超过用户单日收款额度
+    DayReceivedQuotaExceed,
+    /// 超过用户单月收款额度
+    MonthReceivedQuotaExceed,
+    /// 超过用户单日收款次数
+    DayReceivedCountExceed,
+    /// 产品权限校验失败
+    ProductAuthCheckFail,
+    /// 转账关闭
+    OverdueClose,
+    /// 用户身份证校验失败
+    IdCardNotCorrect,
+    /// 用户账户不存在
+    AccountNotExist,
+    /// 转账存在风险
+    TransferRisk,
+    /// 其它失败原因
+    OtherFailReasonType,
+    /// 用户账户收款受限
+    RealnameAccountReceivedQuotaExceed,
+    /// 用户账户收款受限
+    ReceiveAccountNotPermmit,
+    /// 用户账户收款异常，需要用户完善身份信息
+    PayeeAccountAbnormal,
+    /// 商户账户付款受限
+    PayerAccountAbnormal,
+    /// 转账场景暂不可用
+    TransferSceneUnavailable,
+    /// 商户尚未获取该转账场景
+    TransferSceneInvalid,
+    /// 转账备注设置失败
+    TransferRemarkSetFail,
+    /// 收款账户未配置
+    ReceiveAccountNotConfigure,
+    /// 超出用户单月转账收款 20w 限额
+    #[serde(rename = "BLOCK_B2C_USERLIMITAMOUNT_BSRULE_MONTH")]
+    BlockB2cUserLimitAmountBsruleMonth,
+    /// 超出用户单月转账收款限额
+    #[serde(rename = "BLOCK_B2C_USERLIMITAMOUNT_MONTH")]
+    BlockB2cUserLimitAmountMonth,
+    /// 商户员工（转账验密人）已驳回转账
+    MerchantReject,
+    /// 商户员工（转账验密人）超时未验密
+    MerchantNotConfirm,
+    #[serde(other)]
+    Unknown,
 }
 
 /// [发起商家转账](https://pay.weixin.qq.com/doc/v3/merchant/4012458841)
@@ -48,35 +198,244 @@ pub async fn request_batch_transfer<'a>(
     mch_serial_no: &'a str,
     // 微信支付平台证书序列号
     wxpay_serial_no: &'a str,
-) -> Result<serde_json::Value, WxpayApiError> {
-    let url_base = "https://api.mch.weixin.qq.com";
-    let endpoint = "/v3/transfer/batches";
-    let url = format!("{}{}", url_base, endpoint);
-    let method = "POST";
-
-    let body = serde_json::to_string(&body).expect("failed to serialize body");
-
-    let (signature, timestamp, nonce_str) =
-        generate_wxpay_request_signature(method, endpoint, mch_private_key, Some(&body))?;
-
-    let client = Client::new();
-    let response = client.post(url)
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .header("User-Agent", "wechat-vendor-sdk/0.0.0")
-        .header("Wechatpay-Serial", wxpay_serial_no)
-        .header("Authorization", format!("WECHATPAY2-SHA256-RSA2048 mchid=\"{}\",nonce_str=\"{}\",signature=\"{}\",timestamp=\"{}\",serial_no=\"{}\"", 
-            mchid, nonce_str, signature, timestamp, mch_serial_no))
-        .body(body)
-        .send()
+    // 微信支付平台证书公钥，传入时会使用它加密明文的敏感字段
+    wx_public_key: Option<&'a str>,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<BatchTransferResponse, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    let mut body = body;
+    if let Some(wx_public_key) = wx_public_key {
+        body.encrypt_sensitive_fields(&parse_public_key(wx_public_key)?)?;
+    }
+    let body = serde_json::to_string(&body)?;
+    let text = client
+        .send(
+            Method::POST,
+            "/v3/transfer/batches",
+            Some(body),
+            Some(wxpay_serial_no),
+        )
         .await?;
-    let status = response.status();
-    if status == 200 {
-        let result: serde_json::Value = response.json().await?;
-        Ok(result)
-    } else {
-        let result: WxPayFailedResponse = response.json().await?;
-        Err(WxpayApiError::WxpayError(result))
+    Ok(serde_json::from_str(&text)?)
+}
+
+impl WxpayClient {
+    /// [发起商家转账](https://pay.weixin.qq.com/doc/v3/merchant/4012458841)，使用当前的平台证书自动加密敏感字段
+    pub async fn request_batch_transfer(
+        &self,
+        body: &BatchTransferRequestBody,
+    ) -> Result<BatchTransferResponse, WxpayApiError> {
+        let text = self.post_sensitive("/v3/transfer/batches", body).await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+/// 查询转账批次时按状态筛选明细
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferDetailStatusFilter {
+    All,
+    Success,
+    Fail,
+}
+
+impl TransferDetailStatusFilter {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TransferDetailStatusFilter::All => "ALL",
+            TransferDetailStatusFilter::Success => "SUCCESS",
+            TransferDetailStatusFilter::Fail => "FAIL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BatchTransferQuery {
+    /// 是否查询转账明细单，为 `false` 时只返回批次信息
+    pub need_query_detail: bool,
+    /// 明细的分页起始位置，默认为 0
+    pub offset: Option<u32>,
+    /// 明细的最大数量，取值范围 [20, 100]，默认为 20
+    pub limit: Option<u32>,
+    /// 查询明细时必填
+    pub detail_status: Option<TransferDetailStatusFilter>,
+}
+
+impl BatchTransferQuery {
+    fn query_string(&self) -> String {
+        let mut query = format!("need_query_detail={}", self.need_query_detail);
+        if let Some(offset) = self.offset {
+            query.push_str(&format!("&offset={}", offset));
+        }
+        if let Some(limit) = self.limit {
+            query.push_str(&format!("&limit={}", limit));
+        }
+        if let Some(detail_status) = self.detail_status {
+            query.push_str(&format!("&detail_status={}", detail_status.as_str()));
+        }
+        query
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TransferBatch {
+    pub mchid: String,
+    pub out_batch_no: String,
+    pub batch_id: String,
+    pub appid: String,
+    pub batch_status: BatchStatus,
+    /// `API`：API方式发起，`WEB`：页面方式发起
+    pub batch_type: String,
+    pub batch_name: String,
+    pub batch_remark: String,
+    pub close_reason: Option<BatchCloseReason>,
+    pub total_amount: u64,
+    pub total_num: u64,
+    pub create_time: Option<String>,
+    pub update_time: Option<String>,
+    pub success_amount: Option<u64>,
+    pub success_num: Option<u64>,
+    pub fail_amount: Option<u64>,
+    pub fail_num: Option<u64>,
+    pub transfer_scene_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TransferDetailCompact {
+    /// 微信明细单号
+    pub detail_id: String,
+    pub out_detail_no: String,
+    pub detail_status: TransferDetailStatus,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BatchTransferDetailResponseData {
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+    pub transfer_batch: TransferBatch,
+    /// `need_query_detail` 为 `true` 时返回
+    pub transfer_detail_list: Option<Vec<TransferDetailCompact>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TransferDetailResponseData {
+    pub mchid: String,
+    pub out_batch_no: String,
+    pub batch_id: String,
+    pub appid: String,
+    pub out_detail_no: String,
+    pub detail_id: String,
+    pub detail_status: TransferDetailStatus,
+    pub transfer_amount: u64,
+    pub transfer_remark: String,
+    /// 明细状态为 [`TransferDetailStatus::Fail`] 时返回
+    pub fail_reason: Option<TransferFailReason>,
+    pub openid: String,
+    /// 收款用户姓名，使用 [`WxpayClient::decrypt_sensitive`] 解密
+    pub user_name: Option<SensitiveField>,
+    pub initiate_time: String,
+    pub update_time: String,
+}
+
+/// [通过商家批次单号查询批次单](https://pay.weixin.qq.com/doc/v3/merchant/4012458863)
+#[bon::builder]
+pub async fn request_batch_transfer_by_out_batch_no<'a>(
+    out_batch_no: &'a str,
+    query: &'a BatchTransferQuery,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<BatchTransferDetailResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .request_batch_transfer_by_out_batch_no(out_batch_no, query)
+        .await
+}
+
+/// [通过微信批次单号查询批次单](https://pay.weixin.qq.com/doc/v3/merchant/4012458861)
+#[bon::builder]
+pub async fn request_batch_transfer_by_batch_id<'a>(
+    batch_id: &'a str,
+    query: &'a BatchTransferQuery,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<BatchTransferDetailResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .request_batch_transfer_by_batch_id(batch_id, query)
+        .await
+}
+
+/// [通过商家明细单号查询明细单](https://pay.weixin.qq.com/doc/v3/merchant/4012458864)
+#[bon::builder]
+pub async fn request_transfer_detail_by_out_detail_no<'a>(
+    out_batch_no: &'a str,
+    out_detail_no: &'a str,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<TransferDetailResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .request_transfer_detail_by_out_detail_no(out_batch_no, out_detail_no)
+        .await
+}
+
+impl WxpayClient {
+    /// [通过商家批次单号查询批次单](https://pay.weixin.qq.com/doc/v3/merchant/4012458863)
+    pub async fn request_batch_transfer_by_out_batch_no(
+        &self,
+        out_batch_no: &str,
+        query: &BatchTransferQuery,
+    ) -> Result<BatchTransferDetailResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/transfer/batches/out-batch-no/{}?{}",
+            out_batch_no,
+            query.query_string()
+        ))
+        .await
+    }
+
+    /// [通过微信批次单号查询批次单](https://pay.weixin.qq.com/doc/v3/merchant/4012458861)
+    pub async fn request_batch_transfer_by_batch_id(
+        &self,
+        batch_id: &str,
+        query: &BatchTransferQuery,
+    ) -> Result<BatchTransferDetailResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/transfer/batches/batch-id/{}?{}",
+            batch_id,
+            query.query_string()
+        ))
+        .await
+    }
+
+    /// [通过商家明细单号查询明细单](https://pay.weixin.qq.com/doc/v3/merchant/4012458864)
+    pub async fn request_transfer_detail_by_out_detail_no(
+        &self,
+        out_batch_no: &str,
+        out_detail_no: &str,
+    ) -> Result<TransferDetailResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/transfer/batches/out-batch-no/{}/details/out-detail-no/{}",
+            out_batch_no, out_detail_no
+        ))
+        .await
     }
 }
 
@@ -102,9 +461,9 @@ pub struct JsapiOrderRequestBody<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detail: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub scene_info: Option<serde_json::Value>,
+    pub scene_info: Option<OrderSceneInfo<'a>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub settle_info: Option<serde_json::Value>,
+    pub settle_info: Option<SettleInfo>,
 }
 
 #[derive(Debug, Serialize)]
@@ -119,6 +478,77 @@ pub struct JsapiOrderPayer<'a> {
     pub openid: &'a str,
 }
 
+/// 结算信息
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SettleInfo {
+    /// 是否指定分账，为 `true` 时支付成功后资金会被冻结，需要通过[分账](super::profitsharing)解冻
+    pub profit_sharing: bool,
+}
+
+/// 下单时的场景信息
+#[derive(Debug, Serialize)]
+pub struct OrderSceneInfo<'a> {
+    /// 用户终端 ip
+    pub payer_client_ip: &'a str,
+    /// 商户端设备号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store_info: Option<OrderStoreInfo<'a>>,
+    /// 合单、服务商模式 H5 下单时必填，直连 H5 下单使用 [`H5OrderSceneInfo`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub h5_info: Option<OrderH5Info<'a>>,
+}
+
+/// H5 下单时的场景信息，`h5_info` 必填
+#[derive(Debug, Serialize)]
+pub struct H5OrderSceneInfo<'a> {
+    /// 用户终端 ip
+    pub payer_client_ip: &'a str,
+    /// 商户端设备号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store_info: Option<OrderStoreInfo<'a>>,
+    pub h5_info: OrderH5Info<'a>,
+}
+
+/// 商户门店信息
+#[derive(Debug, Serialize)]
+pub struct OrderStoreInfo<'a> {
+    pub id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub area_code: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<&'a str>,
+}
+
+/// H5 场景信息
+#[derive(Debug, Serialize)]
+pub struct OrderH5Info<'a> {
+    #[serde(rename = "type")]
+    pub h5_type: H5Type,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_name: Option<&'a str>,
+}
+
+/// H5 场景类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum H5Type {
+    #[serde(rename = "iOS")]
+    Ios,
+    Android,
+    Wap,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct JsapiOrderResponseData {
     pub prepay_id: String,
@@ -131,35 +561,217 @@ pub async fn request_jsapi_order<'a>(
     mchid: &'a str,
     mch_private_key: &'a str,
     mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
 ) -> Result<JsapiOrderResponseData, WxpayApiError> {
-    let url_base = "https://api.mch.weixin.qq.com";
-    let endpoint = "/v3/pay/transactions/jsapi";
-    let url = format!("{}{}", url_base, endpoint);
-    let method = "POST";
-
-    let body = serde_json::to_string(&body).expect("failed to serialize body");
-
-    let (signature, timestamp, nonce_str) =
-        generate_wxpay_request_signature(method, endpoint, mch_private_key, Some(&body))?;
-
-    let client = Client::new();
-    let response = client.post(url)
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .header("User-Agent", "wechat-vendor-sdk/0.0.0")
-        .header("Authorization", format!("WECHATPAY2-SHA256-RSA2048 mchid=\"{}\",nonce_str=\"{}\",signature=\"{}\",timestamp=\"{}\",serial_no=\"{}\"", 
-            mchid, nonce_str, signature, timestamp, mch_serial_no))
-        .body(body)
-        .send()
-        .await?;
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_jsapi_order(&body).await
+}
+
+impl WxpayClient {
+    /// [JSAPI/小程序下单](https://pay.weixin.qq.com/doc/v3/merchant/4012791897)
+    pub async fn request_jsapi_order(
+        &self,
+        body: &JsapiOrderRequestBody<'_>,
+    ) -> Result<JsapiOrderResponseData, WxpayApiError> {
+        self.post("/v3/pay/transactions/jsapi", body).await
+    }
+
+    /// 使用商户私钥生成 JSAPI/小程序调起支付所需的参数
+    pub fn jsapi_pay_params(
+        &self,
+        app_id: &str,
+        res_data: &JsapiOrderResponseData,
+    ) -> WxpayJsapiPayParams {
+        sign_wxpay_jsapi_pay(app_id, &res_data.prepay_id, self.mch_private_key())
+    }
 
-    let status = response.status();
-    if status == 200 {
-        let result: JsapiOrderResponseData = response.json().await?;
-        Ok(result)
-    } else {
-        let result: WxPayFailedResponse = response.json().await?;
-        Err(WxpayApiError::WxpayError(result))
+    /// JSAPI/小程序下单，并直接返回调起支付所需的参数
+    pub async fn request_jsapi_order_with_pay_params(
+        &self,
+        body: &JsapiOrderRequestBody<'_>,
+    ) -> Result<WxpayJsapiPayParams, WxpayApiError> {
+        let res_data = self.request_jsapi_order(body).await?;
+        Ok(self.jsapi_pay_params(body.appid, &res_data))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct NativeOrderRequestBody<'a> {
+    // 公众号、小程序等的 appid
+    pub appid: &'a str,
+    // 商户号
+    pub mchid: &'a str,
+    pub description: &'a str,
+    pub out_trade_no: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_expire: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attach: Option<&'a str>,
+    pub notify_url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_tag: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub support_fapiao: Option<bool>,
+    pub amount: JsapiOrderAmount<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scene_info: Option<OrderSceneInfo<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settle_info: Option<SettleInfo>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NativeOrderResponseData {
+    /// 二维码链接，用于生成支付二维码，有效期 2 小时
+    pub code_url: String,
+}
+
+/// [Native下单](https://pay.weixin.qq.com/doc/v3/merchant/4012791877)
+#[bon::builder]
+pub async fn request_native_order<'a>(
+    body: NativeOrderRequestBody<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<NativeOrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_native_order(&body).await
+}
+
+impl WxpayClient {
+    /// [Native下单](https://pay.weixin.qq.com/doc/v3/merchant/4012791877)
+    pub async fn request_native_order(
+        &self,
+        body: &NativeOrderRequestBody<'_>,
+    ) -> Result<NativeOrderResponseData, WxpayApiError> {
+        self.post("/v3/pay/transactions/native", body).await
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppOrderRequestBody<'a> {
+    // 移动应用 appid
+    pub appid: &'a str,
+    // 商户号
+    pub mchid: &'a str,
+    pub description: &'a str,
+    pub out_trade_no: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_expire: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attach: Option<&'a str>,
+    pub notify_url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_tag: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub support_fapiao: Option<bool>,
+    pub amount: JsapiOrderAmount<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scene_info: Option<OrderSceneInfo<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settle_info: Option<SettleInfo>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AppOrderResponseData {
+    pub prepay_id: String,
+}
+
+/// [APP下单](https://pay.weixin.qq.com/doc/v3/merchant/4013070347)，调起支付的参数使用 [`generate_wxpay_app_pay_params`](super::utils::generate_wxpay_app_pay_params) 生成
+#[bon::builder]
+pub async fn request_app_order<'a>(
+    body: AppOrderRequestBody<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<AppOrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_app_order(&body).await
+}
+
+impl WxpayClient {
+    /// [APP下单](https://pay.weixin.qq.com/doc/v3/merchant/4013070347)
+    pub async fn request_app_order(
+        &self,
+        body: &AppOrderRequestBody<'_>,
+    ) -> Result<AppOrderResponseData, WxpayApiError> {
+        self.post("/v3/pay/transactions/app", body).await
+    }
+
+    /// 使用商户私钥生成 APP 调起支付所需的参数
+    pub fn app_pay_params(&self, app_id: &str, prepay_id: &str) -> WxpayAppPayParams {
+        sign_wxpay_app_pay(app_id, self.mchid(), prepay_id, self.mch_private_key())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct H5OrderRequestBody<'a> {
+    // 公众号、小程序等的 appid
+    pub appid: &'a str,
+    // 商户号
+    pub mchid: &'a str,
+    pub description: &'a str,
+    pub out_trade_no: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_expire: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attach: Option<&'a str>,
+    pub notify_url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_tag: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub support_fapiao: Option<bool>,
+    pub amount: JsapiOrderAmount<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<serde_json::Value>,
+    pub scene_info: H5OrderSceneInfo<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settle_info: Option<SettleInfo>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct H5OrderResponseData {
+    /// 支付跳转链接，有效期 5 分钟
+    pub h5_url: String,
+}
+
+/// [H5下单](https://pay.weixin.qq.com/doc/v3/merchant/4012791884)
+#[bon::builder]
+pub async fn request_h5_order<'a>(
+    body: H5OrderRequestBody<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<H5OrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_h5_order(&body).await
+}
+
+impl WxpayClient {
+    /// [H5下单](https://pay.weixin.qq.com/doc/v3/merchant/4012791884)
+    pub async fn request_h5_order(
+        &self,
+        body: &H5OrderRequestBody<'_>,
+    ) -> Result<H5OrderResponseData, WxpayApiError> {
+        self.post("/v3/pay/transactions/h5", body).await
     }
 }
 
@@ -170,17 +782,41 @@ pub struct OutTradeNoResponseData {
     pub out_trade_no: String,
     pub transaction_id: Option<String>,
     pub trade_type: Option<String>,
-    pub trade_state: String,
+    pub trade_state: TradeState,
     pub trade_state_desc: String,
     pub bank_type: Option<String>,
     pub attach: Option<String>,
-    pub success_time: Option<String>,
+    pub success_time: Option<DateTime<FixedOffset>>,
     pub payer: Option<Payer>,
     pub amount: Option<Amount>,
     pub scene_info: Option<SceneInfo>,
     pub promotion_detail: Option<Vec<Value>>,
 }
 
+/// 交易状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TradeState {
+    /// 支付成功
+    Success,
+    /// 转入退款
+    Refund,
+    /// 未支付
+    NotPay,
+    /// 已关闭
+    Closed,
+    /// 已撤销（仅付款码支付）
+    Revoked,
+    /// 用户支付中（仅付款码支付）
+    UserPaying,
+    /// 支付失败（仅付款码支付）
+    PayError,
+    /// 微信支付新增的未知状态
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Payer {
     pub openid: Option<String>,
@@ -206,35 +842,62 @@ pub async fn request_order_detail_by_out_trade_no<'a>(
     mchid: &'a str,
     mch_private_key: &'a str,
     mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
 ) -> Result<OutTradeNoResponseData, WxpayApiError> {
-    let url_base = "https://api.mch.weixin.qq.com";
-    let endpoint = format!(
-        "/v3/pay/transactions/out-trade-no/{}?mchid={}",
-        out_trade_no, mchid
-    );
-    let url = format!("{}{}", url_base, endpoint);
-    let method = "GET";
-
-    let (signature, timestamp, nonce_str) =
-        generate_wxpay_request_signature(method, &endpoint, mch_private_key, None)?;
-
-    let client = Client::new();
-    let response = client.get(url)
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .header("User-Agent", "wechat-vendor-sdk/0.0.0")
-        .header("Authorization", format!("WECHATPAY2-SHA256-RSA2048 mchid=\"{}\",nonce_str=\"{}\",signature=\"{}\",timestamp=\"{}\",serial_no=\"{}\"", 
-            mchid, nonce_str, signature, timestamp, mch_serial_no))
-        .send()
-        .await?;
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .request_order_detail_by_out_trade_no(out_trade_no)
+        .await
+}
 
-    let status = response.status();
-    if status == 200 {
-        let result: OutTradeNoResponseData = response.json().await?;
-        Ok(result)
-    } else {
-        let result: WxPayFailedResponse = response.json().await?;
-        Err(WxpayApiError::WxpayError(result))
+impl WxpayClient {
+    /// [商户订单号查询订单](https://pay.weixin.qq.com/doc/v3/merchant/4012791900)
+    pub async fn request_order_detail_by_out_trade_no(
+        &self,
+        out_trade_no: &str,
+    ) -> Result<OutTradeNoResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/pay/transactions/out-trade-no/{}?mchid={}",
+            out_trade_no,
+            self.mchid()
+        ))
+        .await
+    }
+}
+
+/// [微信支付订单号查询订单](https://pay.weixin.qq.com/doc/v3/merchant/4012791899)
+#[bon::builder]
+pub async fn request_order_detail_by_transaction_id<'a>(
+    transaction_id: &'a str,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<OutTradeNoResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .request_order_detail_by_transaction_id(transaction_id)
+        .await
+}
+
+impl WxpayClient {
+    /// [微信支付订单号查询订单](https://pay.weixin.qq.com/doc/v3/merchant/4012791899)，返回格式和商户订单号查询一致
+    pub async fn request_order_detail_by_transaction_id(
+        &self,
+        transaction_id: &str,
+    ) -> Result<OutTradeNoResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/pay/transactions/id/{}?mchid={}",
+            transaction_id,
+            self.mchid()
+        ))
+        .await
     }
 }
 
@@ -251,33 +914,30 @@ pub async fn request_close_order<'a>(
     mchid: &'a str,
     mch_private_key: &'a str,
     mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
 ) -> Result<(), WxpayApiError> {
-    let url_base = "https://api.mch.weixin.qq.com";
-    let endpoint = format!("/v3/pay/transactions/out-trade-no/{}/close", out_trade_no);
-    let url = format!("{}{}", url_base, endpoint);
-    let method = "POST";
-
-    let body = serde_json::to_string(&body).expect("failed to serialize body");
-
-    let (signature, timestamp, nonce_str) =
-        generate_wxpay_request_signature(method, &endpoint, mch_private_key, Some(&body))?;
-
-    let client = Client::new();
-    let response = client.post(url)
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .header("User-Agent", "wechat-vendor-sdk/0.0.0")
-        .header("Authorization", format!("WECHATPAY2-SHA256-RSA2048 mchid=\"{}\",nonce_str=\"{}\",signature=\"{}\",timestamp=\"{}\",serial_no=\"{}\"", 
-            mchid, nonce_str, signature, timestamp, mch_serial_no))
-        .body(body)
-        .send()
-        .await?;
-    let status = response.status();
-    if status == 204 {
-        Ok(())
-    } else {
-        let result: WxPayFailedResponse = response.json().await?;
-        Err(WxpayApiError::WxpayError(result))
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .post_without_response(
+            &format!("/v3/pay/transactions/out-trade-no/{}/close", out_trade_no),
+            &body,
+        )
+        .await
+}
+
+impl WxpayClient {
+    /// [关闭订单](https://pay.weixin.qq.com/doc/v3/merchant/4012791901)
+    pub async fn request_close_order(&self, out_trade_no: &str) -> Result<(), WxpayApiError> {
+        self.post_without_response(
+            &format!("/v3/pay/transactions/out-trade-no/{}/close", out_trade_no),
+            &CloseOrderRequestBody {
+                mchid: self.mchid(),
+            },
+        )
+        .await
     }
 }
 
@@ -288,11 +948,29 @@ pub enum OrderId {
     TransactionId(String),
 }
 
+/// 退款出资账户
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RefundFundsAccount {
+    /// 可用余额
+    Available,
+    /// 不可用余额
+    Unavailable,
+}
+
+/// 退款出资账户及金额
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RefundAmountFrom {
+    pub account: RefundFundsAccount,
+    /// 对应账户出资金额，单位为分
+    pub amount: i64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct RefundAmount<'a> {
     pub refund: i32,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub from: Option<Vec<Value>>,
+    pub from: Option<Vec<RefundAmountFrom>>,
     pub total: i32,
     pub currency: &'a str,
 }
@@ -313,6 +991,48 @@ pub struct RefundOrderRequestBody<'a> {
     pub goods_detail: Option<Vec<Value>>,
 }
 
+/// 退款状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RefundStatus {
+    /// 退款成功
+    Success,
+    /// 退款关闭
+    Closed,
+    /// 退款处理中
+    Processing,
+    /// 退款异常，退款到银行发现用户的卡作废或者冻结了，可以使用[异常退款](WxpayClient::request_apply_abnormal_refund)处理
+    Abnormal,
+    /// 微信支付新增的未知状态
+    #[serde(other)]
+    Unknown,
+}
+
+/// 退款金额信息，单位为分
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RefundDetailAmount {
+    /// 订单总金额
+    pub total: i64,
+    /// 退款金额
+    pub refund: i64,
+    /// 退款出资的账户类型及金额，申请退款时指定了出资账户才会返回
+    #[serde(default)]
+    pub from: Vec<RefundAmountFrom>,
+    /// 用户实际支付金额
+    pub payer_total: i64,
+    /// 用户退款金额，不包含所有优惠券金额
+    pub payer_refund: i64,
+    /// 应结退款金额，去掉非充值代金券退款金额后的退款金额
+    pub settlement_refund: i64,
+    /// 应结订单金额
+    pub settlement_total: i64,
+    /// 优惠退款金额
+    pub discount_refund: i64,
+    pub currency: String,
+    /// 手续费退款金额
+    pub refund_fee: Option<i64>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct RefundOrderResponseData {
     pub refund_id: String,
@@ -321,11 +1041,11 @@ pub struct RefundOrderResponseData {
     pub out_trade_no: String,
     pub channel: String,
     pub user_received_account: String,
-    pub success_time: Option<String>,
+    pub success_time: Option<DateTime<FixedOffset>>,
     pub create_time: String,
-    pub status: String,
+    pub status: RefundStatus,
     pub funds_account: String,
-    pub amount: Value,
+    pub amount: RefundDetailAmount,
     pub promotion_detail: Option<Vec<Value>>,
 }
 
@@ -336,53 +1056,28 @@ pub async fn request_refund_order<'a>(
     mchid: &'a str,
     mch_private_key: &'a str,
     mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
 ) -> Result<RefundOrderResponseData, WxpayApiError> {
-    let url_base = "https://api.mch.weixin.qq.com";
-    let endpoint = "/v3/refund/domestic/refunds";
-    let url = format!("{}{}", url_base, endpoint);
-    let method = "POST";
-
-    let body = serde_json::to_string(&body).expect("failed to serialize body");
-
-    let (signature, timestamp, nonce_str) =
-        generate_wxpay_request_signature(method, endpoint, mch_private_key, Some(&body))?;
-
-    let client = Client::new();
-    let response = client.post(url)
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .header("User-Agent", "wechat-vendor-sdk/0.0.0")
-        .header("Authorization", format!("WECHATPAY2-SHA256-RSA2048 mchid=\"{}\",nonce_str=\"{}\",signature=\"{}\",timestamp=\"{}\",serial_no=\"{}\"", 
-            mchid, nonce_str, signature, timestamp, mch_serial_no))
-        .body(body)
-        .send()
-        .await?;
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_refund_order(&body).await
+}
 
-    let status = response.status();
-    if status == 200 {
-        let result: RefundOrderResponseData = response.json().await?;
-        Ok(result)
-    } else {
-        let result: WxPayFailedResponse = response.json().await?;
-        Err(WxpayApiError::WxpayError(result))
+impl WxpayClient {
+    /// [退款申请](https://pay.weixin.qq.com/doc/v3/merchant/4012791903)
+    pub async fn request_refund_order(
+        &self,
+        body: &RefundOrderRequestBody<'_>,
+    ) -> Result<RefundOrderResponseData, WxpayApiError> {
+        self.post("/v3/refund/domestic/refunds", body).await
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct RefundDetailResponseData {
-    pub refund_id: String,
-    pub out_refund_no: String,
-    pub transaction_id: String,
-    pub out_trade_no: String,
-    pub channel: String,
-    pub user_received_account: String,
-    pub success_time: Option<String>,
-    pub create_time: String,
-    pub status: String,
-    pub funds_account: String,
-    pub amount: Value,
-    pub promotion_detail: Option<Vec<Value>>,
-}
+/// 查询单笔退款返回的数据和退款申请一致
+pub type RefundDetailResponseData = RefundOrderResponseData;
+
 /// [查询单笔退款（通过商户退款单号）](https://pay.weixin.qq.com/doc/v3/merchant/4012791904)
 #[bon::builder]
 pub async fn request_refund_detail<'a>(
@@ -390,31 +1085,185 @@ pub async fn request_refund_detail<'a>(
     mchid: &'a str,
     mch_private_key: &'a str,
     mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
 ) -> Result<RefundDetailResponseData, WxpayApiError> {
-    let url_base = "https://api.mch.weixin.qq.com";
-    let endpoint = format!("/v3/refund/domestic/refunds/{}", out_refund_no);
-    let url = format!("{}{}", url_base, endpoint);
-    let method = "GET";
-
-    let (signature, timestamp, nonce_str) =
-        generate_wxpay_request_signature(method, &endpoint, mch_private_key, None)?;
-
-    let client = Client::new();
-    let response = client.get(url)
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .header("User-Agent", "wechat-vendor-sdk/0.0.0")
-        .header("Authorization", format!("WECHATPAY2-SHA256-RSA2048 mchid=\"{}\",nonce_str=\"{}\",signature=\"{}\",timestamp=\"{}\",serial_no=\"{}\"", 
-            mchid, nonce_str, signature, timestamp, mch_serial_no))
-        .send()
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_refund_detail(out_refund_no).await
+}
+
+impl WxpayClient {
+    /// [查询单笔退款（通过商户退款单号）](https://pay.weixin.qq.com/doc/v3/merchant/4012791904)
+    ///
+    /// 直连商户的 APIv3 只提供按商户退款单号查询，没有按微信支付退款单号（`refund_id`）查询的接口
+    pub async fn request_refund_detail(
+        &self,
+        out_refund_no: &str,
+    ) -> Result<RefundDetailResponseData, WxpayApiError> {
+        self.get(&format!("/v3/refund/domestic/refunds/{}", out_refund_no))
+            .await
+    }
+}
+
+/// 异常退款的退款方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AbnormalRefundType {
+    /// 退款到用户银行卡
+    UserBankCard,
+    /// 退款至交易商户银行账户
+    MerchantBankCard,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AbnormalRefundRequestBody {
+    pub out_refund_no: String,
+    #[serde(rename = "type")]
+    pub refund_type: AbnormalRefundType,
+    /// 开户银行，退款至用户银行卡时必填
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bank_type: Option<String>,
+    /// 收款银行卡号，退款至用户银行卡时必填，需要加密
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bank_account: Option<SensitiveField>,
+    /// 收款用户姓名，退款至用户银行卡时必填，需要加密
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub real_name: Option<SensitiveField>,
+}
+
+impl SensitiveFields for AbnormalRefundRequestBody {
+    fn sensitive_fields(&mut self) -> Vec<&mut SensitiveField> {
+        self.bank_account
+            .iter_mut()
+            .chain(self.real_name.iter_mut())
+            .collect()
+    }
+}
+
+/// [发起异常退款](https://pay.weixin.qq.com/doc/v3/merchant/4013421201)
+#[bon::builder]
+pub async fn request_apply_abnormal_refund<'a>(
+    // 微信支付退款单号
+    refund_id: &'a str,
+    body: AbnormalRefundRequestBody,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 微信支付平台证书序列号
+    wxpay_serial_no: &'a str,
+    // 微信支付平台证书公钥，传入时会使用它加密明文的敏感字段
+    wx_public_key: Option<&'a str>,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<RefundOrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    let mut body = body;
+    if let Some(wx_public_key) = wx_public_key {
+        body.encrypt_sensitive_fields(&parse_public_key(wx_public_key)?)?;
+    }
+    let body = serde_json::to_string(&body)?;
+    let text = client
+        .send(
+            Method::POST,
+            &format!(
+                "/v3/refund/domestic/refunds/{}/apply-abnormal-refund",
+                refund_id
+            ),
+            Some(body),
+            Some(wxpay_serial_no),
+        )
         .await?;
+    Ok(serde_json::from_str(&text)?)
+}
 
-    let status = response.status();
-    if status == 200 {
-        let result: RefundDetailResponseData = response.json().await?;
-        Ok(result)
-    } else {
-        let result: WxPayFailedResponse = response.json().await?;
-        Err(WxpayApiError::WxpayError(result))
+impl WxpayClient {
+    /// [发起异常退款](https://pay.weixin.qq.com/doc/v3/merchant/4013421201)，退款状态为 [`RefundStatus::Abnormal`] 时使用，
+    /// 使用当前的平台证书自动加密银行卡号和姓名
+    pub async fn request_apply_abnormal_refund(
+        &self,
+        refund_id: &str,
+        body: &AbnormalRefundRequestBody,
+    ) -> Result<RefundOrderResponseData, WxpayApiError> {
+        let text = self
+            .post_sensitive(
+                &format!(
+                    "/v3/refund/domestic/refunds/{}/apply-abnormal-refund",
+                    refund_id
+                ),
+                body,
+            )
+            .await?;
+        Ok(serde_json::from_str(&text)?)
     }
 }
+
+#[test]
+fn test_deserialize_order_detail() {
+    let data: OutTradeNoResponseData = serde_json::from_str(
+        r#"{"appid":"wxd678efh567hg6787","mchid":"1230000109","out_trade_no":"1217752501201407033233368018","transaction_id":"1217752501201407033233368018","trade_type":"JSAPI","trade_state":"USERPAYING","trade_state_desc":"支付中","bank_type":"CMC","attach":"自定义数据","success_time":"2018-06-08T10:34:56+08:00","payer":{"openid":"oUpF8uMuAJO_M2pxb1Q9zNjWeS6o"},"amount":{"total":100,"payer_total":100,"currency":"CNY","payer_currency":"CNY"}}"#,
+    )
+    .unwrap();
+    assert_eq!(data.trade_state, TradeState::UserPaying);
+    assert_eq!(
+        data.success_time.unwrap().to_rfc3339(),
+        "2018-06-08T10:34:56+08:00"
+    );
+
+    let data: OutTradeNoResponseData = serde_json::from_str(
+        r#"{"appid":"wxd678efh567hg6787","mchid":"1230000109","out_trade_no":"1217752501201407033233368018","trade_state":"NOTPAY","trade_state_desc":"未支付"}"#,
+    )
+    .unwrap();
+    assert_eq!(data.trade_state, TradeState::NotPay);
+    assert!(data.success_time.is_none());
+}
+
+#[test]
+fn test_deserialize_batch_transfer_detail() {
+    let data: BatchTransferDetailResponseData = serde_json::from_str(
+        r#"{"limit":20,"offset":0,"transfer_batch":{"mchid":"1900001109","out_batch_no":"plfk2020042013","batch_id":"1030000071100999991182020050700019480001","appid":"wxf636efh567hg4356","batch_status":"FINISHED","batch_type":"API","batch_name":"2019年1月深圳分部报销单","batch_remark":"2019年1月深圳分部报销单","total_amount":4000000,"total_num":200,"create_time":"2015-05-20T13:29:35.120+08:00","update_time":"2015-05-20T13:29:35.120+08:00","success_amount":3900000,"success_num":199,"fail_amount":100000,"fail_num":1,"transfer_scene_id":"1000"},"transfer_detail_list":[{"detail_id":"1040000071100999991182020050700019500100","out_detail_no":"x23zy545Bd5436","detail_status":"FAIL"}]}"#,
+    )
+    .unwrap();
+    assert_eq!(data.transfer_batch.batch_status, BatchStatus::Finished);
+    assert_eq!(
+        data.transfer_detail_list.unwrap()[0].detail_status,
+        TransferDetailStatus::Fail
+    );
+    assert_eq!(
+        BatchTransferQuery {
+            need_query_detail: true,
+            detail_status: Some(TransferDetailStatusFilter::All),
+            ..Default::default()
+        }
+        .query_string(),
+        "need_query_detail=true&detail_status=ALL"
+    );
+
+    let data: TransferDetailResponseData = serde_json::from_str(
+        r#"{"mchid":"1900001109","out_batch_no":"plfk2020042013","batch_id":"1030000071100999991182020050700019480001","appid":"wxf636efh567hg4356","out_detail_no":"x23zy545Bd5436","detail_id":"1040000071100999991182020050700019500100","detail_status":"FAIL","transfer_amount":200000,"transfer_remark":"2020年4月报销","fail_reason":"BLOCK_B2C_USERLIMITAMOUNT_MONTH","openid":"o-MYE42l80oelYMDE34nYD456Xoy","user_name":"757b340b45ebef5467rter35gf464344v3542sdf4t6re4tb4f54ty45t4yyry45","initiate_time":"2015-05-20T13:29:35.120+08:00","update_time":"2015-05-20T13:29:35.120+08:00"}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        data.fail_reason,
+        Some(TransferFailReason::BlockB2cUserLimitAmountMonth)
+    );
+    assert!(data.user_name.unwrap().is_encrypted());
+}
+
+#[test]
+fn test_deserialize_refund_detail() {
+    let data: RefundDetailResponseData = serde_json::from_str(
+        r#"{"refund_id":"50000000382019052709732678859","out_refund_no":"1217752501201407033233368018","transaction_id":"1217752501201407033233368018","out_trade_no":"1217752501201407033233368018","channel":"ORIGINAL","user_received_account":"招商银行信用卡0403","success_time":"2020-12-01T16:18:12+08:00","create_time":"2020-12-01T16:18:12+08:00","status":"SUCCESS","funds_account":"UNSETTLED","amount":{"total":100,"refund":100,"from":[{"account":"AVAILABLE","amount":444}],"payer_total":90,"payer_refund":90,"settlement_refund":100,"settlement_total":100,"discount_refund":10,"currency":"CNY","refund_fee":100}}"#,
+    )
+    .unwrap();
+    assert_eq!(data.status, RefundStatus::Success);
+    assert_eq!(data.amount.discount_refund, 10);
+    assert_eq!(data.amount.from[0].account, RefundFundsAccount::Available);
+    assert_eq!(
+        data.success_time.unwrap().to_rfc3339(),
+        "2020-12-01T16:18:12+08:00"
+    );
+}
@@ -0,0 +1,707 @@
+//! [交易账单](https://pay.weixin.qq.com/doc/v3/merchant/4012791906)和[资金账单](https://pay.weixin.qq.com/doc/v3/merchant/4012791907)的申请、下载与解析
+//!
+//! ```ignore
+//! let bill = client
+//!     .download_trade_bill(&TradeBillQuery {
+//!         bill_date: "2024-06-01",
+//!         bill_type: Some(TradeBillType::All),
+//!         gzip: true,
+//!     })
+//!     .await?;
+//! for row in bill.rows {
+//!     println!("{} {:?} {}", row.out_trade_no, row.trade_state, row.settlement_total_fee);
+//! }
+//! ```
+use std::{collections::HashMap, io::Read};
+
+use flate2::read::GzDecoder;
+use reqwest::{Method, Url};
+use serde::{
+    de::{value, DeserializeOwned, IntoDeserializer},
+    Deserialize, Serialize,
+};
+use sha1::{Digest, Sha1};
+
+use super::{
+    api::{temporary_client, TradeState, WXPAY_DEFAULT_BASE_URL},
+    client::WxpayClient,
+    error::{WxPayFailedResponse, WxpayApiError},
+};
+
+/// 交易账单类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeBillType {
+    /// 当日所有订单（不含充值退款订单）
+    All,
+    /// 当日成功支付的订单（不含充值退款订单）
+    Success,
+    /// 当日退款订单（不含充值退款订单）
+    Refund,
+}
+
+impl TradeBillType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TradeBillType::All => "ALL",
+            TradeBillType::Success => "SUCCESS",
+            TradeBillType::Refund => "REFUND",
+        }
+    }
+}
+
+/// 资金账户类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FundFlowAccountType {
+    /// 基本账户
+    Basic,
+    /// 运营账户
+    Operation,
+    /// 手续费账户
+    Fees,
+}
+
+impl FundFlowAccountType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            FundFlowAccountType::Basic => "BASIC",
+            FundFlowAccountType::Operation => "OPERATION",
+            FundFlowAccountType::Fees => "FEES",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TradeBillQuery<'a> {
+    /// 账单日期，格式为 `yyyy-MM-DD`，仅支持三个月内的账单
+    pub bill_date: &'a str,
+    /// 不填则默认为 [`TradeBillType::All`]
+    pub bill_type: Option<TradeBillType>,
+    /// 是否使用 gzip 压缩账单文件
+    pub gzip: bool,
+}
+
+impl TradeBillQuery<'_> {
+    fn url_path(&self) -> String {
+        let mut url_path = format!("/v3/bill/tradebill?bill_date={}", self.bill_date);
+        if let Some(bill_type) = self.bill_type {
+            url_path.push_str(&format!("&bill_type={}", bill_type.as_str()));
+        }
+        if self.gzip {
+            url_path.push_str("&tar_type=GZIP");
+        }
+        url_path
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FundFlowBillQuery<'a> {
+    /// 账单日期，格式为 `yyyy-MM-DD`，仅支持三个月内的账单
+    pub bill_date: &'a str,
+    /// 不填则默认为 [`FundFlowAccountType::Basic`]
+    pub account_type: Option<FundFlowAccountType>,
+    /// 是否使用 gzip 压缩账单文件
+    pub gzip: bool,
+}
+
+impl FundFlowBillQuery<'_> {
+    fn url_path(&self) -> String {
+        let mut url_path = format!("/v3/bill/fundflowbill?bill_date={}", self.bill_date);
+        if let Some(account_type) = self.account_type {
+            url_path.push_str(&format!("&account_type={}", account_type.as_str()));
+        }
+        if self.gzip {
+            url_path.push_str("&tar_type=GZIP");
+        }
+        url_path
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BillResponseData {
+    /// 固定为 `SHA1`
+    pub hash_type: String,
+    /// 原始账单（gzip 需要解压缩）的摘要值
+    pub hash_value: String,
+    /// 账单下载地址，30 秒内有效
+    pub download_url: String,
+}
+
+/// [申请交易账单](https://pay.weixin.qq.com/doc/v3/merchant/4012791906)
+#[bon::builder]
+pub async fn request_trade_bill<'a>(
+    query: TradeBillQuery<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<BillResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_trade_bill(&query).await
+}
+
+/// [申请资金账单](https://pay.weixin.qq.com/doc/v3/merchant/4012791907)
+#[bon::builder]
+pub async fn request_fund_flow_bill<'a>(
+    query: FundFlowBillQuery<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<BillResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_fund_flow_bill(&query).await
+}
+
+impl WxpayClient {
+    /// [申请交易账单](https://pay.weixin.qq.com/doc/v3/merchant/4012791906)
+    pub async fn request_trade_bill(
+        &self,
+        query: &TradeBillQuery<'_>,
+    ) -> Result<BillResponseData, WxpayApiError> {
+        self.get(&query.url_path()).await
+    }
+
+    /// [申请资金账单](https://pay.weixin.qq.com/doc/v3/merchant/4012791907)
+    pub async fn request_fund_flow_bill(
+        &self,
+        query: &FundFlowBillQuery<'_>,
+    ) -> Result<BillResponseData, WxpayApiError> {
+        self.get(&query.url_path()).await
+    }
+
+    /// [下载账单](https://pay.weixin.qq.com/doc/v3/merchant/4012791908)，gzip 压缩的账单会被解压，并使用 `hash_value` 校验账单内容
+    ///
+    /// 下载账单的响应没有签名，不会验证响应签名
+    pub async fn download_bill(&self, bill: &BillResponseData) -> Result<String, WxpayApiError> {
+        let download_url =
+            Url::parse(&bill.download_url).map_err(|e| WxpayApiError::BillError(e.to_string()))?;
+        // 签名使用下载地址的路径和参数，请求发往 client 配置的域名
+        let url_path = match download_url.query() {
+            Some(query) => format!("{}?{}", download_url.path(), query),
+            None => download_url.path().to_string(),
+        };
+
+        let response = self
+            .signed_request(Method::GET, &url_path, None)
+            .send()
+            .await?;
+        let status = response.status();
+        let bytes = response.bytes().await?;
+        if !status.is_success() {
+            let result: WxPayFailedResponse = serde_json::from_slice(&bytes)?;
+            return Err(WxpayApiError::WxpayError(result));
+        }
+
+        let content = if bytes.starts_with(&[0x1f, 0x8b]) {
+            let mut content = Vec::new();
+            GzDecoder::new(bytes.as_ref())
+                .read_to_end(&mut content)
+                .map_err(|e| WxpayApiError::BillError(e.to_string()))?;
+            content
+        } else {
+            bytes.to_vec()
+        };
+
+        verify_bill_hash(&content, &bill.hash_type, &bill.hash_value)?;
+        String::from_utf8(content).map_err(|e| WxpayApiError::BillError(e.to_string()))
+    }
+
+    /// 申请并下载交易账单，解析为 [`TradeBill`]
+    pub async fn download_trade_bill(
+        &self,
+        query: &TradeBillQuery<'_>,
+    ) -> Result<TradeBill, WxpayApiError> {
+        let bill = self.request_trade_bill(query).await?;
+        TradeBill::parse(&self.download_bill(&bill).await?)
+    }
+
+    /// 申请并下载资金账单，解析为 [`FundFlowBill`]
+    pub async fn download_fund_flow_bill(
+        &self,
+        query: &FundFlowBillQuery<'_>,
+    ) -> Result<FundFlowBill, WxpayApiError> {
+        let bill = self.request_fund_flow_bill(query).await?;
+        FundFlowBill::parse(&self.download_bill(&bill).await?)
+    }
+}
+
+/// 校验账单内容的 SHA1 摘要
+pub fn verify_bill_hash(
+    content: &[u8],
+    hash_type: &str,
+    hash_value: &str,
+) -> Result<(), WxpayApiError> {
+    if !hash_type.eq_ignore_ascii_case("SHA1") {
+        return Err(WxpayApiError::BillError(format!(
+            "unsupported hash type {}",
+            hash_type
+        )));
+    }
+    let actual = format!("{:x}", Sha1::digest(content));
+    if actual.eq_ignore_ascii_case(hash_value) {
+        Ok(())
+    } else {
+        Err(WxpayApiError::BillHashMismatch)
+    }
+}
+
+/// 交易账单中的退款类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BillRefundType {
+    /// 原路退款
+    Original,
+    /// 退回到余额
+    Balance,
+    /// 原账户异常退到其他余额账户
+    OtherBalance,
+    /// 原银行卡异常退到其他银行卡
+    OtherBankcard,
+    /// 微信支付新增的未知类型
+    #[serde(other)]
+    Unknown,
+}
+
+/// 交易账单中的退款状态，取值和[退款查询](super::api::RefundStatus)不同
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum BillRefundStatus {
+    /// 退款成功
+    Success,
+    /// 退款关闭
+    RefundClose,
+    /// 退款处理中
+    Processing,
+    /// 退款异常
+    Change,
+    /// 微信支付新增的未知状态
+    #[serde(other)]
+    Unknown,
+}
+
+/// 交易账单的一行，金额单位为分。不同账单类型的列不同，账单中没有的列为空字符串或 0
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TradeBillRow {
+    /// 交易时间
+    pub trade_time: String,
+    /// 公众账号ID
+    pub appid: String,
+    /// 商户号
+    pub mchid: String,
+    /// 特约商户号
+    pub sub_mchid: String,
+    /// 设备号
+    pub device_info: String,
+    /// 微信订单号
+    pub transaction_id: String,
+    /// 商户订单号
+    pub out_trade_no: String,
+    /// 用户标识
+    pub openid: String,
+    /// 交易类型
+    pub trade_type: String,
+    /// 交易状态，支付记录为 [`TradeState::Success`]，退款记录为 [`TradeState::Refund`]
+    pub trade_state: TradeState,
+    /// 付款银行
+    pub bank_type: String,
+    /// 货币种类
+    pub currency: String,
+    /// 应结订单金额
+    pub settlement_total_fee: i64,
+    /// 代金券金额
+    pub coupon_fee: i64,
+    /// 微信退款单号
+    pub refund_id: String,
+    /// 商户退款单号
+    pub out_refund_no: String,
+    /// 退款金额
+    pub refund_fee: i64,
+    /// 充值券退款金额
+    pub coupon_refund_fee: i64,
+    /// 退款类型，非退款记录为 `None`
+    pub refund_type: Option<BillRefundType>,
+    /// 退款状态，非退款记录为 `None`
+    pub refund_status: Option<BillRefundStatus>,
+    /// 商品名称
+    pub goods_name: String,
+    /// 商户数据包
+    pub attach: String,
+    /// 手续费，单位为元，精确到小数点后 5 位，保留账单原文
+    pub fee: String,
+    /// 费率
+    pub rate: String,
+    /// 订单金额
+    pub total_fee: i64,
+    /// 申请退款金额
+    pub apply_refund_fee: i64,
+    /// 费率备注
+    pub rate_remark: String,
+}
+
+/// 交易账单的汇总，金额单位为分
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TradeBillSummary {
+    /// 总交易单数
+    pub total_count: u64,
+    /// 应结订单总金额
+    pub settlement_total_fee: i64,
+    /// 退款总金额
+    pub refund_fee: i64,
+    /// 充值券退款总金额
+    pub coupon_refund_fee: i64,
+    /// 手续费总金额，单位为元，精确到小数点后 5 位，保留账单原文
+    pub fee: String,
+    /// 订单总金额
+    pub total_fee: i64,
+    /// 申请退款总金额
+    pub apply_refund_fee: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TradeBill {
+    pub rows: Vec<TradeBillRow>,
+    pub summary: TradeBillSummary,
+}
+
+impl TradeBill {
+    /// 解析下载到的交易账单
+    pub fn parse(content: &str) -> Result<Self, WxpayApiError> {
+        let table = BillTable::parse(content)?;
+        let rows = table
+            .rows
+            .iter()
+            .map(|row| {
+                Ok(TradeBillRow {
+                    trade_time: row.text("交易时间"),
+                    appid: row.text("公众账号ID"),
+                    mchid: row.text("商户号"),
+                    sub_mchid: row.text("特约商户号"),
+                    device_info: row.text("设备号"),
+                    transaction_id: row.text("微信订单号"),
+                    out_trade_no: row.text("商户订单号"),
+                    openid: row.text("用户标识"),
+                    trade_type: row.text("交易类型"),
+                    trade_state: row.state("交易状态").unwrap_or_default(),
+                    bank_type: row.text("付款银行"),
+                    currency: row.text("货币种类"),
+                    settlement_total_fee: row.amount("应结订单金额")?,
+                    coupon_fee: row.amount("代金券金额")?,
+                    refund_id: row.text("微信退款单号"),
+                    out_refund_no: row.text("商户退款单号"),
+                    refund_fee: row.amount("退款金额")?,
+                    coupon_refund_fee: row.amount("充值券退款金额")?,
+                    refund_type: row.state("退款类型"),
+                    refund_status: row.state("退款状态"),
+                    goods_name: row.text("商品名称"),
+                    attach: row.text("商户数据包"),
+                    fee: row.text("手续费"),
+                    rate: row.text("费率"),
+                    total_fee: row.amount("订单金额")?,
+                    apply_refund_fee: row.amount("申请退款金额")?,
+                    rate_remark: row.text("费率备注"),
+                })
+            })
+            .collect::<Result<_, WxpayApiError>>()?;
+        let summary = &table.summary;
+        Ok(Self {
+            rows,
+            summary: TradeBillSummary {
+                total_count: summary.count("总交易单数")?,
+                settlement_total_fee: summary.amount("应结订单总金额")?,
+                refund_fee: summary.amount("退款总金额")?,
+                coupon_refund_fee: summary.amount("充值券退款总金额")?,
+                fee: summary.text("手续费总金额"),
+                total_fee: summary.amount("订单总金额")?,
+                apply_refund_fee: summary.amount("申请退款总金额")?,
+            },
+        })
+    }
+}
+
+/// 资金账单的一行，金额单位为分
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FundFlowBillRow {
+    /// 记账时间
+    pub accounting_time: String,
+    /// 微信支付业务单号
+    pub transaction_id: String,
+    /// 资金流水单号
+    pub flow_id: String,
+    /// 业务名称
+    pub business_name: String,
+    /// 业务类型
+    pub business_type: String,
+    /// 收支类型，`收入` 或 `支出`
+    pub income_type: String,
+    /// 收支金额
+    pub amount: i64,
+    /// 账户结余
+    pub balance: i64,
+    /// 资金变更提交申请人
+    pub applicant: String,
+    /// 备注
+    pub remark: String,
+    /// 业务凭证号
+    pub voucher_no: String,
+}
+
+/// 资金账单的汇总，金额单位为分
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FundFlowBillSummary {
+    /// 资金流水总笔数
+    pub total_count: u64,
+    /// 收入笔数
+    pub income_count: u64,
+    /// 收入金额
+    pub income_amount: i64,
+    /// 支出笔数
+    pub expense_count: u64,
+    /// 支出金额
+    pub expense_amount: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FundFlowBill {
+    pub rows: Vec<FundFlowBillRow>,
+    pub summary: FundFlowBillSummary,
+}
+
+impl FundFlowBill {
+    /// 解析下载到的资金账单
+    pub fn parse(content: &str) -> Result<Self, WxpayApiError> {
+        let table = BillTable::parse(content)?;
+        let rows = table
+            .rows
+            .iter()
+            .map(|row| {
+                Ok(FundFlowBillRow {
+                    accounting_time: row.text("记账时间"),
+                    transaction_id: row.text("微信支付业务单号"),
+                    flow_id: row.text("资金流水单号"),
+                    business_name: row.text("业务名称"),
+                    business_type: row.text("业务类型"),
+                    income_type: row.text("收支类型"),
+                    amount: row.amount("收支金额（元）")?,
+                    balance: row.amount("账户结余（元）")?,
+                    applicant: row.text("资金变更提交申请人"),
+                    remark: row.text("备注"),
+                    voucher_no: row.text("业务凭证号"),
+                })
+            })
+            .collect::<Result<_, WxpayApiError>>()?;
+        let summary = &table.summary;
+        Ok(Self {
+            rows,
+            summary: FundFlowBillSummary {
+                total_count: summary.count("资金流水总笔数")?,
+                income_count: summary.count("收入笔数")?,
+                income_amount: summary.amount("收入金额")?,
+                expense_count: summary.count("支出笔数")?,
+                expense_amount: summary.amount("支出金额")?,
+            },
+        })
+    }
+}
+
+/// 账单文件的通用结构：表头、以 `` ` `` 开头的明细行、汇总表头、汇总行
+struct BillTable {
+    rows: Vec<BillRecord>,
+    summary: BillRecord,
+}
+
+/// 列名 -> 去掉 `` ` `` 前缀后的值
+struct BillRecord(HashMap<String, String>);
+
+impl BillTable {
+    fn parse(content: &str) -> Result<Self, WxpayApiError> {
+        let mut lines = content
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .filter(|line| !line.is_empty());
+
+        let header = split_header(lines.next().ok_or_else(|| bill_error("empty bill"))?);
+        let mut rows = Vec::new();
+        let summary_header = loop {
+            let line = lines
+                .next()
+                .ok_or_else(|| bill_error("missing summary section"))?;
+            if line.starts_with('`') {
+                rows.push(BillRecord::new(&header, line)?);
+            } else {
+                break split_header(line);
+            }
+        };
+        let summary_line = lines
+            .next()
+            .ok_or_else(|| bill_error("missing summary section"))?;
+        let summary = BillRecord::new(&summary_header, summary_line)?;
+        Ok(Self { rows, summary })
+    }
+}
+
+fn split_header(line: &str) -> Vec<String> {
+    line.split(',')
+        .map(|name| name.trim().to_string())
+        .collect()
+}
+
+impl BillRecord {
+    fn new(header: &[String], line: &str) -> Result<Self, WxpayApiError> {
+        // 每个值都以 ` 开头，按 ",`" 分割可以避免值中的逗号被误分割
+        let values: Vec<&str> = line
+            .strip_prefix('`')
+            .ok_or_else(|| bill_error(format!("invalid bill line: {}", line)))?
+            .split(",`")
+            .collect();
+        if values.len() != header.len() {
+            return Err(bill_error(format!(
+                "expected {} columns, got {}: {}",
+                header.len(),
+                values.len(),
+                line
+            )));
+        }
+        Ok(Self(
+            header
+                .iter()
+                .cloned()
+                .zip(values.into_iter().map(|value| value.trim().to_string()))
+                .collect(),
+        ))
+    }
+
+    fn text(&self, column: &str) -> String {
+        self.0.get(column).cloned().unwrap_or_default()
+    }
+
+    /// 解析为状态枚举，空值为 `None`，未知的取值由枚举的 `Unknown` 兜底
+    fn state<T: DeserializeOwned>(&self, column: &str) -> Option<T> {
+        self.0
+            .get(column)
+            .filter(|value| !value.is_empty())
+            .and_then(|value| {
+                T::deserialize(IntoDeserializer::<value::Error>::into_deserializer(
+                    value.as_str(),
+                ))
+                .ok()
+            })
+    }
+
+    fn amount(&self, column: &str) -> Result<i64, WxpayApiError> {
+        match self.0.get(column) {
+            Some(value) if !value.is_empty() => yuan_to_fen(value)
+                .ok_or_else(|| bill_error(format!("invalid amount {}: {}", column, value))),
+            _ => Ok(0),
+        }
+    }
+
+    fn count(&self, column: &str) -> Result<u64, WxpayApiError> {
+        match self.0.get(column) {
+            Some(value) if !value.is_empty() => value
+                .parse()
+                .map_err(|_| bill_error(format!("invalid count {}: {}", column, value))),
+            _ => Ok(0),
+        }
+    }
+}
+
+/// 将以元为单位的金额（如 `0.01`、`-100.5`）转换为分
+pub(crate) fn yuan_to_fen(value: &str) -> Option<i64> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let (yuan, fen) = value.split_once('.').unwrap_or((value, ""));
+    if yuan.is_empty()
+        || fen.len() > 2
+        || !(yuan.chars().chain(fen.chars())).all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let fen = format!("{:0<2}", fen).parse::<i64>().ok()?;
+    let amount = yuan
+        .parse::<i64>()
+        .ok()?
+        .checked_mul(100)?
+        .checked_add(fen)?;
+    Some(if negative { -amount } else { amount })
+}
+
+fn bill_error(message: impl Into<String>) -> WxpayApiError {
+    WxpayApiError::BillError(message.into())
+}
+
+#[cfg(test)]
+pub(crate) const TRADE_BILL_FIXTURE: &str = include_str!("../../fixtures/wxpay/trade_bill_all.csv");
+
+#[test]
+fn test_parse_trade_bill() {
+    let bill = TradeBill::parse(TRADE_BILL_FIXTURE).unwrap();
+    assert_eq!(bill.rows.len(), 4);
+    let row = &bill.rows[0];
+    assert_eq!(row.trade_time, "2024-06-01 10:02:11");
+    assert_eq!(row.out_trade_no, "ORDER0001");
+    assert_eq!(row.trade_state, TradeState::Success);
+    assert_eq!(row.refund_type, None);
+    assert_eq!(row.refund_status, None);
+    assert_eq!(row.settlement_total_fee, 1000);
+    assert_eq!(row.fee, "0.06000");
+    assert_eq!(row.rate, "0.60%");
+    assert_eq!(bill.rows[1].goods_name, "商品,带逗号");
+    assert_eq!(bill.rows[3].trade_state, TradeState::Refund);
+    assert_eq!(bill.rows[3].refund_type, Some(BillRefundType::Original));
+    assert_eq!(bill.rows[3].refund_status, Some(BillRefundStatus::Success));
+    assert_eq!(bill.rows[3].refund_fee, 500);
+    assert_eq!(
+        bill.summary,
+        TradeBillSummary {
+            total_count: 4,
+            settlement_total_fee: 4300,
+            refund_fee: 500,
+            coupon_refund_fee: 0,
+            fee: "0.23000".into(),
+            total_fee: 4300,
+            apply_refund_fee: 500,
+        }
+    );
+}
+
+#[test]
+fn test_parse_fund_flow_bill() {
+    let bill =
+        FundFlowBill::parse(include_str!("../../fixtures/wxpay/fund_flow_bill.csv")).unwrap();
+    assert_eq!(bill.rows.len(), 2);
+    assert_eq!(bill.rows[0].income_type, "收入");
+    assert_eq!(bill.rows[0].amount, 994);
+    assert_eq!(bill.rows[1].amount, 500);
+    assert_eq!(bill.rows[1].balance, 494);
+    assert_eq!(bill.summary.income_amount, 994);
+    assert_eq!(bill.summary.expense_count, 1);
+}
+
+#[test]
+fn test_verify_bill_hash() {
+    let content = TRADE_BILL_FIXTURE.as_bytes();
+    let hash_value = format!("{:X}", Sha1::digest(content));
+    assert!(verify_bill_hash(content, "SHA1", &hash_value).is_ok());
+    assert!(matches!(
+        verify_bill_hash(b"tampered", "SHA1", &hash_value),
+        Err(WxpayApiError::BillHashMismatch)
+    ));
+}
+
+#[test]
+fn test_yuan_to_fen() {
+    assert_eq!(yuan_to_fen("0.01"), Some(1));
+    assert_eq!(yuan_to_fen("12.5"), Some(1250));
+    assert_eq!(yuan_to_fen("-3"), Some(-300));
+    assert_eq!(yuan_to_fen("1.234"), None);
+    assert_eq!(yuan_to_fen("abc"), None);
+}
@@ -1,9 +1,19 @@
-use chrono::TimeZone;
-use rsa::{pkcs8::DecodePublicKey, Pkcs1v15Sign};
+use chrono::{DateTime, FixedOffset, TimeZone};
+use reqwest::header::HeaderMap;
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use super::{api::OutTradeNoResponseData, error::WxpayApiError};
+use super::{
+    api::{BatchCloseReason, BatchStatus, OutTradeNoResponseData, RefundStatus},
+    client::WxpayClient,
+    combine::CombineCallbackResourceData,
+    error::WxpayApiError,
+    partner::{PartnerPayCallbackResourceData, PartnerRefundCallbackResourceData},
+    profitsharing::ProfitSharingCallbackResourceData,
+    transfer::TransferBillCallbackResourceData,
+    utils::parse_public_key,
+};
 
 /// 用于验证微信支付的回调请求签名
 ///
@@ -21,12 +31,31 @@ pub fn verify_wxpay_callback_signature(
     body: &str,
     // 是否跳过时间戳检查
     skip_timestamp_check: Option<bool>,
+) -> Result<bool, WxpayApiError> {
+    let public_key = parse_public_key(wx_public_key)?;
+    verify_wxpay_signature(
+        &public_key,
+        signature,
+        timestamp,
+        nonce,
+        body,
+        skip_timestamp_check.unwrap_or(false),
+    )
+}
+
+/// 使用已解析的公钥验证微信支付的签名，回调通知和 api 响应的验签规则相同
+pub(crate) fn verify_wxpay_signature(
+    public_key: &RsaPublicKey,
+    signature: &str,
+    timestamp: &str,
+    nonce: &str,
+    body: &str,
+    skip_timestamp_check: bool,
 ) -> Result<bool, WxpayApiError> {
     use base64::{engine::general_purpose, Engine as _};
     use rsa::sha2::{Digest, Sha256};
-    use rsa::RsaPublicKey;
 
-    if skip_timestamp_check.unwrap_or(false) == false {
+    if !skip_timestamp_check {
         // 验证时间戳
         use chrono::{Duration, Utc};
         let current_time = Utc::now();
@@ -52,8 +81,6 @@ pub fn verify_wxpay_callback_signature(
     hasher.update(message.as_bytes());
     let hash = hasher.finalize();
 
-    let public_key = RsaPublicKey::from_public_key_pem(wx_public_key)
-        .map_err(|e| WxpayApiError::InvalidPublicKey)?;
     let scheme = Pkcs1v15Sign::new::<Sha256>();
     let res = public_key.verify(scheme, &hash, signature_bytes.as_slice());
     match res {
@@ -74,6 +101,24 @@ pub fn decrypt_wxpay_callback_resource(
     nonce: &str,
     associated_data: &str,
 ) -> Result<Value, WxpayApiError> {
+    let plaintext = decrypt_wxpay_resource(apiv3_key, ciphertext, nonce, associated_data)?;
+    let val = serde_json::from_slice(&plaintext);
+    match val {
+        Ok(val) => Ok(val),
+        Err(e) => {
+            tracing::error!("decrypt wxpay callback resource to json failed: {}", e);
+            Err(WxpayApiError::DecryptFailed)
+        }
+    }
+}
+
+/// 使用 APIv3 密钥进行 AEAD_AES_256_GCM 解密，回调通知和平台证书下载接口的加密规则相同
+pub(crate) fn decrypt_wxpay_resource(
+    apiv3_key: &str,
+    ciphertext: &str,
+    nonce: &str,
+    associated_data: &str,
+) -> Result<Vec<u8>, WxpayApiError> {
     use aes_gcm::aead::{Aead, Payload};
     use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
     use base64::{engine::general_purpose, Engine as _};
@@ -82,22 +127,257 @@ pub fn decrypt_wxpay_callback_resource(
 
     let cipher = Aes256Gcm::new_from_slice(apiv3_key.as_bytes())?;
     let payload = Payload {
-        msg: &ciphertext.as_slice(),
-        aad: &associated_data.as_bytes(),
+        msg: ciphertext.as_slice(),
+        aad: associated_data.as_bytes(),
     };
     let nonce = Nonce::from_slice(nonce.as_bytes());
 
-    let plaintext = cipher
+    cipher
         .decrypt(nonce, payload)
-        .map_err(|_| WxpayApiError::DecryptFailed)?;
-    let val = serde_json::from_slice(&plaintext);
-    match val {
-        Ok(val) => Ok(val),
-        Err(e) => {
-            tracing::error!("decrypt wxpay callback resource to json failed: {}", e);
-            Err(WxpayApiError::DecryptFailed)
+        .map_err(|_| WxpayApiError::DecryptFailed)
+}
+
+impl WxpayClient {
+    /// 使用回调请求头 `Wechatpay-Serial` 对应的平台证书公钥或微信支付公钥验证回调签名，未知的平台证书序列号会触发平台证书的重新下载
+    pub async fn verify_callback_signature(
+        &self,
+        // 从请求头获取的平台证书序列号
+        serial_no: &str,
+        // 从请求头获取的签名
+        signature: &str,
+        // 从请求头获取的时间戳
+        timestamp: &str,
+        // 从请求头获取的随机字符串
+        nonce: &str,
+        // 请求体
+        body: &str,
+    ) -> Result<bool, WxpayApiError> {
+        let public_key = self.platform_public_key(serial_no).await?;
+        verify_wxpay_signature(&public_key, signature, timestamp, nonce, body, false)
+    }
+
+    /// 使用 APIv3 密钥解密回调通知中的 resource
+    pub fn decrypt_callback_resource(
+        &self,
+        ciphertext: &str,
+        nonce: &str,
+        associated_data: &str,
+    ) -> Result<Value, WxpayApiError> {
+        decrypt_wxpay_callback_resource(self.apiv3_key()?, ciphertext, nonce, associated_data)
+    }
+
+    /// 处理回调通知：验证签名、解密 resource，并按 `event_type` 解析为 [`WxpayCallbackEvent`]
+    ///
+    /// ```ignore
+    /// async fn notify(headers: HeaderMap, body: String) -> (StatusCode, String) {
+    ///     let response = match client.handle_callback(&headers, &body).await {
+    ///         Ok(WxpayCallbackEvent::TransactionSuccess(data)) => {
+    ///             // 处理支付成功
+    ///             WxpayCallbackResponse::success()
+    ///         }
+    ///         Ok(_) => WxpayCallbackResponse::success(),
+    ///         Err(e) => WxpayCallbackResponse::fail(e.to_string()),
+    ///     };
+    ///     (StatusCode::from_u16(response.status_code()).unwrap(), response.to_body())
+    /// }
+    /// ```
+    pub async fn handle_callback(
+        &self,
+        headers: &HeaderMap,
+        body: &str,
+    ) -> Result<WxpayCallbackEvent, WxpayApiError> {
+        let serial_no = callback_header(headers, "Wechatpay-Serial")?;
+        let public_key = self.platform_public_key(serial_no).await?;
+        parse_wxpay_callback(&public_key, self.apiv3_key()?, headers, body)
+    }
+}
+
+/// 和 [`WxpayClient::handle_callback`] 相同，使用给定的公钥和 APIv3 密钥处理回调通知
+///
+/// * `wx_public_key` - 回调请求头 `Wechatpay-Serial` 对应的平台证书公钥或微信支付公钥（pem 格式）
+pub fn handle_wxpay_callback(
+    wx_public_key: &str,
+    apiv3_key: &str,
+    headers: &HeaderMap,
+    body: &str,
+) -> Result<WxpayCallbackEvent, WxpayApiError> {
+    let public_key = parse_public_key(wx_public_key)?;
+    parse_wxpay_callback(&public_key, apiv3_key, headers, body)
+}
+
+fn callback_header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, WxpayApiError> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| WxpayApiError::InvalidCallbackSignature(format!("missing header {}", name)))
+}
+
+fn parse_wxpay_callback(
+    public_key: &RsaPublicKey,
+    apiv3_key: &str,
+    headers: &HeaderMap,
+    body: &str,
+) -> Result<WxpayCallbackEvent, WxpayApiError> {
+    let verified = verify_wxpay_signature(
+        public_key,
+        callback_header(headers, "Wechatpay-Signature")?,
+        callback_header(headers, "Wechatpay-Timestamp")?,
+        callback_header(headers, "Wechatpay-Nonce")?,
+        body,
+        false,
+    )?;
+    if !verified {
+        return Err(WxpayApiError::InvalidCallbackSignature(
+            "signature mismatch".into(),
+        ));
+    }
+
+    let notification: WxpayCallbackNotification = serde_json::from_str(body)?;
+    let resource = notification.resource;
+    let data = decrypt_wxpay_callback_resource(
+        apiv3_key,
+        &resource.ciphertext,
+        &resource.nonce,
+        resource.associated_data.as_deref().unwrap_or_default(),
+    )?;
+    // 服务商模式的通知数据中包含服务商商户号
+    let partner = data.get("sp_mchid").is_some();
+    let event = match notification.event_type.as_str() {
+        // 合单支付和普通支付的通知类型相同，通过合单商户订单号区分。
+        // 服务商模式的合单通知同样包含 `sp_mchid`，也解析为合单通知，子商户号在 `sub_orders` 中
+        "TRANSACTION.SUCCESS" if data.get("combine_out_trade_no").is_some() => {
+            WxpayCallbackEvent::CombineTransactionSuccess(serde_json::from_value(data)?)
+        }
+        "TRANSACTION.SUCCESS" if partner => {
+            WxpayCallbackEvent::PartnerTransactionSuccess(serde_json::from_value(data)?)
+        }
+        "TRANSACTION.SUCCESS" => {
+            WxpayCallbackEvent::TransactionSuccess(serde_json::from_value(data)?)
+        }
+        "REFUND.SUCCESS" if partner => {
+            WxpayCallbackEvent::PartnerRefundSuccess(serde_json::from_value(data)?)
+        }
+        "REFUND.ABNORMAL" if partner => {
+            WxpayCallbackEvent::PartnerRefundAbnormal(serde_json::from_value(data)?)
+        }
+        "REFUND.CLOSED" if partner => {
+            WxpayCallbackEvent::PartnerRefundClosed(serde_json::from_value(data)?)
+        }
+        "REFUND.SUCCESS" => WxpayCallbackEvent::RefundSuccess(serde_json::from_value(data)?),
+        "REFUND.ABNORMAL" => WxpayCallbackEvent::RefundAbnormal(serde_json::from_value(data)?),
+        "REFUND.CLOSED" => WxpayCallbackEvent::RefundClosed(serde_json::from_value(data)?),
+        "PROFITSHARING.SUCCESS" => {
+            WxpayCallbackEvent::ProfitSharingSuccess(serde_json::from_value(data)?)
+        }
+        "MCHTRANSFER.BATCH.FINISHED" => {
+            WxpayCallbackEvent::BatchTransferFinished(serde_json::from_value(data)?)
+        }
+        "MCHTRANSFER.BILL.FINISHED" => {
+            WxpayCallbackEvent::TransferBillFinished(serde_json::from_value(data)?)
+        }
+        "MCHTRANSFER.BATCH.CLOSED" => {
+            WxpayCallbackEvent::BatchTransferClosed(serde_json::from_value(data)?)
+        }
+        _ => WxpayCallbackEvent::Other {
+            event_type: notification.event_type,
+            data,
+        },
+    };
+    Ok(event)
+}
+
+/// 回调通知的请求体
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WxpayCallbackNotification {
+    pub id: String,
+    pub create_time: String,
+    pub event_type: String,
+    pub resource_type: String,
+    pub summary: String,
+    pub resource: WxpayCallbackResource,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WxpayCallbackResource {
+    pub algorithm: String,
+    pub ciphertext: String,
+    pub associated_data: Option<String>,
+    pub original_type: String,
+    pub nonce: String,
+}
+
+/// 按 `event_type` 区分的回调通知解密后的数据
+#[derive(Debug)]
+pub enum WxpayCallbackEvent {
+    /// TRANSACTION.SUCCESS
+    TransactionSuccess(WxpayPayCallbackResourceData),
+    /// 合单支付的 TRANSACTION.SUCCESS，包括服务商模式的合单支付
+    CombineTransactionSuccess(CombineCallbackResourceData),
+    /// REFUND.SUCCESS
+    RefundSuccess(WxpayRefundCallbackResourceData),
+    /// REFUND.ABNORMAL
+    RefundAbnormal(WxpayRefundCallbackResourceData),
+    /// REFUND.CLOSED
+    RefundClosed(WxpayRefundCallbackResourceData),
+    /// 服务商模式的 TRANSACTION.SUCCESS
+    PartnerTransactionSuccess(PartnerPayCallbackResourceData),
+    /// 服务商模式的 REFUND.SUCCESS
+    PartnerRefundSuccess(PartnerRefundCallbackResourceData),
+    /// 服务商模式的 REFUND.ABNORMAL
+    PartnerRefundAbnormal(PartnerRefundCallbackResourceData),
+    /// 服务商模式的 REFUND.CLOSED
+    PartnerRefundClosed(PartnerRefundCallbackResourceData),
+    /// PROFITSHARING.SUCCESS
+    ProfitSharingSuccess(ProfitSharingCallbackResourceData),
+    /// MCHTRANSFER.BATCH.FINISHED
+    BatchTransferFinished(WxpayBatchTransferCallbackResourceDataFinished),
+    /// MCHTRANSFER.BATCH.CLOSED
+    BatchTransferClosed(WxpayBatchTransferCallbackResourceDataClosed),
+    /// MCHTRANSFER.BILL.FINISHED
+    TransferBillFinished(TransferBillCallbackResourceData),
+    /// 其他暂未解析的通知类型
+    Other { event_type: String, data: Value },
+}
+
+/// 回调通知的应答，处理失败时微信支付会按策略重新发送通知
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WxpayCallbackResponse {
+    pub code: String,
+    pub message: String,
+}
+
+impl WxpayCallbackResponse {
+    pub fn success() -> Self {
+        Self {
+            code: "SUCCESS".into(),
+            message: "成功".into(),
+        }
+    }
+
+    pub fn fail(message: impl Into<String>) -> Self {
+        Self {
+            code: "FAIL".into(),
+            message: message.into(),
         }
     }
+
+    pub fn is_success(&self) -> bool {
+        self.code == "SUCCESS"
+    }
+
+    /// 应答的 http 状态码，成功为 200，失败为 500
+    pub fn status_code(&self) -> u16 {
+        if self.is_success() {
+            200
+        } else {
+            500
+        }
+    }
+
+    /// 应答的 json 响应体
+    pub fn to_body(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -106,10 +386,10 @@ pub struct WxpayBatchTransferCallbackResourceDataClosed {
     pub mchid: String,
     pub out_batch_no: String,
     pub batch_id: String,
-    pub batch_status: String,
+    pub batch_status: BatchStatus,
     pub total_num: i32,
     pub total_amount: i32,
-    pub close_reason: Option<String>,
+    pub close_reason: Option<BatchCloseReason>,
     pub update_time: String,
 }
 
@@ -118,7 +398,7 @@ pub struct WxpayBatchTransferCallbackResourceDataClosed {
 pub struct WxpayBatchTransferCallbackResourceDataFinished {
     pub out_batch_no: String,
     pub batch_id: String,
-    pub batch_status: String,
+    pub batch_status: BatchStatus,
     pub total_amount: i32,
     pub total_num: i32,
     pub success_amount: i32,
@@ -147,8 +427,8 @@ pub struct WxpayRefundCallbackResourceData {
     pub out_trade_no: String,
     pub refund_id: String,
     pub out_refund_no: String,
-    pub refund_status: String,
-    pub success_time: Option<String>,
+    pub refund_status: RefundStatus,
+    pub success_time: Option<DateTime<FixedOffset>>,
     pub user_received_account: String,
     pub amount: RefundCallbackAmount,
 }
@@ -208,3 +488,146 @@ fn test_decrypt_wxpay_callback_resource() {
     let result = decrypt_wxpay_callback_resource(apiv3_key, ciphertext, nonce, "mch_payment");
     println!("result: {:?}", result);
 }
+
+/// 使用 `private_key` 签名、`apiv3_key` 加密，构造回调通知的请求头和请求体
+#[cfg(test)]
+fn encrypted_callback(
+    private_key: &rsa::RsaPrivateKey,
+    apiv3_key: &str,
+    event_type: &str,
+    original_type: &str,
+    resource: &str,
+) -> (HeaderMap, String) {
+    use aes_gcm::aead::{Aead, Payload};
+    use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+    use base64::{engine::general_purpose, Engine as _};
+
+    use super::utils::{generate_noncestr, sign_sha256_rsa};
+
+    let resource_nonce = generate_noncestr(12);
+    let ciphertext = Aes256Gcm::new_from_slice(apiv3_key.as_bytes())
+        .unwrap()
+        .encrypt(
+            Nonce::from_slice(resource_nonce.as_bytes()),
+            Payload {
+                msg: resource.as_bytes(),
+                aad: original_type.as_bytes(),
+            },
+        )
+        .unwrap();
+    let body = serde_json::json!({
+        "id": "EV-2018022511223320873",
+        "create_time": "2018-06-08T10:34:56+08:00",
+        "resource_type": "encrypt-resource",
+        "event_type": event_type,
+        "summary": "通知",
+        "resource": {
+            "original_type": original_type,
+            "algorithm": "AEAD_AES_256_GCM",
+            "ciphertext": general_purpose::STANDARD.encode(ciphertext),
+            "associated_data": original_type,
+            "nonce": resource_nonce,
+        }
+    })
+    .to_string();
+
+    let timestamp = chrono::Utc::now().timestamp().to_string();
+    let nonce = generate_noncestr(32);
+    let signature = sign_sha256_rsa(
+        private_key,
+        &format!("{}\n{}\n{}\n", timestamp, nonce, body),
+    );
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Wechatpay-Serial",
+        "PUB_KEY_ID_0114232134912410000000".parse().unwrap(),
+    );
+    headers.insert("Wechatpay-Timestamp", timestamp.parse().unwrap());
+    headers.insert("Wechatpay-Nonce", nonce.parse().unwrap());
+    headers.insert("Wechatpay-Signature", signature.parse().unwrap());
+    (headers, body)
+}
+
+#[test]
+fn test_handle_wxpay_callback() {
+    use rsa::{pkcs8::EncodePublicKey, RsaPrivateKey};
+
+    use super::utils::generate_noncestr;
+
+    let apiv3_key = generate_noncestr(32);
+    let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+    let wx_public_key = RsaPublicKey::from(&private_key)
+        .to_public_key_pem(Default::default())
+        .unwrap();
+
+    let resource = r#"{"mchid":"1900000100","out_trade_no":"1217752501201407033233368318","transaction_id":"1217752501201407033233368018","refund_id":"50000000382019052709732678859","out_refund_no":"1217752501201407033233368018","refund_status":"SUCCESS","success_time":"2018-06-08T10:34:56+08:00","user_received_account":"招商银行信用卡0403","amount":{"total":999,"refund":999,"payer_total":999,"payer_refund":999}}"#;
+    let (headers, body) = encrypted_callback(
+        &private_key,
+        &apiv3_key,
+        "REFUND.SUCCESS",
+        "refund",
+        resource,
+    );
+
+    match handle_wxpay_callback(&wx_public_key, &apiv3_key, &headers, &body).unwrap() {
+        WxpayCallbackEvent::RefundSuccess(data) => assert_eq!(data.amount.refund, 999),
+        event => panic!("unexpected event: {:?}", event),
+    }
+    assert!(matches!(
+        handle_wxpay_callback(&wx_public_key, &apiv3_key, &headers, "{}"),
+        Err(WxpayApiError::InvalidCallbackSignature(_))
+    ));
+    assert_eq!(
+        WxpayCallbackResponse::fail("失败").to_body(),
+        r#"{"code":"FAIL","message":"失败"}"#
+    );
+}
+
+#[test]
+fn test_handle_partner_callback() {
+    use rsa::{pkcs8::EncodePublicKey, RsaPrivateKey};
+
+    use super::utils::generate_noncestr;
+
+    let apiv3_key = generate_noncestr(32);
+    let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+    let wx_public_key = RsaPublicKey::from(&private_key)
+        .to_public_key_pem(Default::default())
+        .unwrap();
+    let handle = |event_type: &str, original_type: &str, resource: &str| {
+        let (headers, body) = encrypted_callback(
+            &private_key,
+            &apiv3_key,
+            event_type,
+            original_type,
+            resource,
+        );
+        handle_wxpay_callback(&wx_public_key, &apiv3_key, &headers, &body).unwrap()
+    };
+
+    let resource = r#"{"sp_appid":"wx8888888888888888","sp_mchid":"1230000109","sub_appid":"wxd678efh567hg6999","sub_mchid":"1900000109","out_trade_no":"1217752501201407033233368018","transaction_id":"1217752501201407033233368018","trade_type":"JSAPI","trade_state":"SUCCESS","trade_state_desc":"支付成功","bank_type":"CMC","success_time":"2018-06-08T10:34:56+08:00","payer":{"sp_openid":"oUpF8uMuAJO_M2pxb1Q9zNjWeS6o"},"amount":{"total":100,"payer_total":100,"currency":"CNY","payer_currency":"CNY"}}"#;
+    match handle("TRANSACTION.SUCCESS", "transaction", resource) {
+        WxpayCallbackEvent::PartnerTransactionSuccess(data) => {
+            assert_eq!(data.sub_mchid, "1900000109")
+        }
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    let resource = r#"{"sp_mchid":"1230000109","sub_mchid":"1900000109","out_trade_no":"1217752501201407033233368318","transaction_id":"1217752501201407033233368018","refund_id":"50000000382019052709732678859","out_refund_no":"1217752501201407033233368018","refund_status":"SUCCESS","success_time":"2018-06-08T10:34:56+08:00","user_received_account":"招商银行信用卡0403","amount":{"total":999,"refund":999,"payer_total":999,"payer_refund":999}}"#;
+    match handle("REFUND.SUCCESS", "refund", resource) {
+        WxpayCallbackEvent::PartnerRefundSuccess(data) => {
+            assert_eq!(data.sub_mchid, "1900000109");
+            assert_eq!(data.amount.refund, 999);
+        }
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    // 服务商模式的合单通知按合单通知解析
+    let resource = r#"{"combine_appid":"wxd678efh567hg6787","combine_mchid":"1230000109","sp_mchid":"1230000109","combine_out_trade_no":"P20150806125346","sub_orders":[{"mchid":"1230000109","sub_mchid":"1900000109","trade_type":"JSAPI","trade_state":"SUCCESS","bank_type":"CMC","success_time":"2015-05-20T13:29:35.120+08:00","transaction_id":"1009660380201506130728806387","out_trade_no":"20150806125346","amount":{"total_amount":10,"currency":"CNY","payer_amount":10,"payer_currency":"CNY"}}],"combine_payer_info":{"openid":"oUpF8uMuAJO_M2pxb1Q9zNjWeS6o"}}"#;
+    match handle("TRANSACTION.SUCCESS", "transaction", resource) {
+        WxpayCallbackEvent::CombineTransactionSuccess(data) => {
+            assert_eq!(data.sub_orders[0].sub_mchid.as_deref(), Some("1900000109"))
+        }
+        event => panic!("unexpected event: {:?}", event),
+    }
+}
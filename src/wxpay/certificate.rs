@@ -0,0 +1,414 @@
+//! 微信支付平台证书的下载、解密与自动更新，以及微信支付公钥
+//!
+//! 配置了 APIv3 密钥时，[`WxpayClient`] 会在首次需要平台证书时自动下载，之后定期更新；
+//! 遇到未知序列号的证书时（比如微信支付已经开始使用轮换后的新证书签名）也会立即重新下载。
+//!
+//! 新入驻的商户使用[微信支付公钥](https://pay.weixin.qq.com/doc/v3/merchant/4012153196)代替平台证书，
+//! 此时回调和响应的 `Wechatpay-Serial` 为公钥 ID（`PUB_KEY_ID_` 开头）。两种方式可以同时配置，
+//! 验签时按 `Wechatpay-Serial` 自动选择；配置了微信支付公钥时不再定期下载平台证书，加密敏感信息也优先使用微信支付公钥。
+//!
+use std::{
+    collections::HashMap,
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, FixedOffset};
+use reqwest::{header::HeaderMap, Method};
+use rsa::{pkcs8::DecodePublicKey, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use x509_cert::{
+    der::{DecodePem, Encode},
+    Certificate,
+};
+
+use super::{
+    callback::decrypt_wxpay_resource,
+    client::{verify_response_signature, WxpayClient},
+    error::WxpayApiError,
+};
+
+/// 平台证书的定期更新间隔
+const REFRESH_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// 微信支付公钥 ID 的前缀
+pub const WXPAY_PUBLIC_KEY_ID_PREFIX: &str = "PUB_KEY_ID_";
+
+/// 因未知序列号触发下载的最小间隔，避免伪造的序列号导致频繁请求
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EncryptCertificate {
+    pub algorithm: String,
+    pub nonce: String,
+    pub associated_data: String,
+    pub ciphertext: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PlatformCertificateItem {
+    pub serial_no: String,
+    pub effective_time: String,
+    pub expire_time: String,
+    pub encrypt_certificate: EncryptCertificate,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CertificatesResponseData {
+    pub data: Vec<PlatformCertificateItem>,
+}
+
+/// 从 x509 证书（pem 格式）中提取公钥
+pub fn extract_public_key_from_certificate(
+    certificate: &str,
+) -> Result<RsaPublicKey, WxpayApiError> {
+    let certificate = Certificate::from_pem(certificate.as_bytes())
+        .map_err(|_| WxpayApiError::InvalidCertificate)?;
+    let public_key_der = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|_| WxpayApiError::InvalidCertificate)?;
+    RsaPublicKey::from_public_key_der(&public_key_der).map_err(|_| WxpayApiError::InvalidPublicKey)
+}
+
+#[derive(Clone)]
+struct PlatformPublicKey {
+    public_key: RsaPublicKey,
+    /// 手动配置的公钥没有过期时间
+    expire_time: Option<DateTime<FixedOffset>>,
+}
+
+/// client 内缓存的平台证书公钥和微信支付公钥
+pub(crate) struct PlatformCertificates {
+    /// 平台证书序列号 -> 公钥
+    keys: RwLock<HashMap<String, PlatformPublicKey>>,
+    /// (公钥 ID, 微信支付公钥)
+    wxpay_public_key: Option<(String, RsaPublicKey)>,
+    auto_refresh: bool,
+    /// 最近一次尝试下载的时间，无论成功与否，用于限制下载频率
+    last_attempted_at: Mutex<Option<Instant>>,
+    /// 最近一次成功下载的时间
+    last_refreshed_at: Mutex<Option<Instant>>,
+    refresh_lock: tokio::sync::Mutex<()>,
+}
+
+impl PlatformCertificates {
+    pub(crate) fn new(
+        keys: HashMap<String, RsaPublicKey>,
+        wxpay_public_key: Option<(String, RsaPublicKey)>,
+        auto_refresh: bool,
+    ) -> Self {
+        let keys = keys
+            .into_iter()
+            .map(|(serial_no, public_key)| {
+                (
+                    serial_no,
+                    PlatformPublicKey {
+                        public_key,
+                        expire_time: None,
+                    },
+                )
+            })
+            .collect();
+        Self {
+            keys: RwLock::new(keys),
+            wxpay_public_key,
+            auto_refresh,
+            last_attempted_at: Mutex::new(None),
+            last_refreshed_at: Mutex::new(None),
+            refresh_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn get(&self, serial_no: &str) -> Option<RsaPublicKey> {
+        if let Some((public_key_id, public_key)) = &self.wxpay_public_key {
+            if public_key_id == serial_no {
+                return Some(public_key.clone());
+            }
+        }
+        self.keys
+            .read()
+            .unwrap()
+            .get(serial_no)
+            .map(|key| key.public_key.clone())
+    }
+
+    /// 优先使用微信支付公钥，否则为过期时间最晚的证书，即最新的证书
+    fn newest(&self) -> Option<(String, RsaPublicKey)> {
+        if let Some(wxpay_public_key) = &self.wxpay_public_key {
+            return Some(wxpay_public_key.clone());
+        }
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .max_by_key(|(_, key)| key.expire_time)
+            .map(|(serial_no, key)| (serial_no.clone(), key.public_key.clone()))
+    }
+
+    /// 用下载到的证书更新缓存，同时移除已过期的证书
+    fn update(&self, downloaded: HashMap<String, PlatformPublicKey>) {
+        let now = chrono::Utc::now();
+        let mut keys = self.keys.write().unwrap();
+        keys.retain(|_, key| key.expire_time.is_none_or(|expire_time| expire_time > now));
+        keys.extend(downloaded);
+        *self.last_refreshed_at.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// 配置了微信支付公钥时不再需要定期下载平台证书；下载失败后也要间隔 `MIN_REFRESH_INTERVAL` 才重试
+    fn is_refresh_due(&self) -> bool {
+        self.wxpay_public_key.is_none()
+            && elapsed_since(&self.last_refreshed_at)
+                .is_none_or(|elapsed| elapsed > REFRESH_INTERVAL)
+            && self.can_refresh_for_unknown_serial()
+    }
+
+    fn can_refresh_for_unknown_serial(&self) -> bool {
+        elapsed_since(&self.last_attempted_at).is_none_or(|elapsed| elapsed > MIN_REFRESH_INTERVAL)
+    }
+}
+
+fn elapsed_since(instant: &Mutex<Option<Instant>>) -> Option<Duration> {
+    instant.lock().unwrap().map(|instant| instant.elapsed())
+}
+
+impl WxpayClient {
+    /// [下载平台证书](https://pay.weixin.qq.com/doc/v3/merchant/4012551764)，返回的证书内容是加密的，
+    /// 一般直接使用 [`WxpayClient::refresh_platform_certificates`]
+    ///
+    /// 该接口的响应需要使用下载到的证书验签，这里不验证响应签名
+    pub async fn request_certificates(&self) -> Result<CertificatesResponseData, WxpayApiError> {
+        let (_, _, data) = self.download_certificates().await?;
+        Ok(data)
+    }
+
+    /// 下载平台证书，同时返回响应头和响应体，用于之后验证响应签名
+    async fn download_certificates(
+        &self,
+    ) -> Result<(HeaderMap, String, CertificatesResponseData), WxpayApiError> {
+        let (headers, text) = self
+            .send_without_verify(Method::GET, "/v3/certificates", None, None)
+            .await?;
+        let data = serde_json::from_str(&text)?;
+        Ok((headers, text, data))
+    }
+
+    /// 下载并解密平台证书，更新到 client 的缓存中并移除已过期的证书，返回下载到的证书序列号
+    pub async fn refresh_platform_certificates(&self) -> Result<Vec<String>, WxpayApiError> {
+        let apiv3_key = self.apiv3_key()?;
+        let certificates = self.platform_certificates();
+        *certificates.last_attempted_at.lock().unwrap() = Some(Instant::now());
+        let (headers, text, data) = self.download_certificates().await?;
+
+        let mut downloaded = HashMap::new();
+        for item in data.data {
+            let certificate = decrypt_wxpay_resource(
+                apiv3_key,
+                &item.encrypt_certificate.ciphertext,
+                &item.encrypt_certificate.nonce,
+                &item.encrypt_certificate.associated_data,
+            )?;
+            let certificate =
+                String::from_utf8(certificate).map_err(|_| WxpayApiError::InvalidCertificate)?;
+            let expire_time = DateTime::parse_from_rfc3339(&item.expire_time)
+                .map_err(|_| WxpayApiError::InvalidCertificate)?;
+            downloaded.insert(
+                item.serial_no,
+                PlatformPublicKey {
+                    public_key: extract_public_key_from_certificate(&certificate)?,
+                    expire_time: Some(expire_time),
+                },
+            );
+        }
+
+        if self.verify_response_enabled() {
+            // 证书下载接口的响应由其中的某个证书签名，解密能防止中间人篡改，验签确认证书确实来自微信支付
+            let serial_no = headers
+                .get("Wechatpay-Serial")
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default();
+            let public_key = match downloaded.get(serial_no) {
+                Some(key) => key.public_key.clone(),
+                None => certificates
+                    .get(serial_no)
+                    .ok_or_else(|| WxpayApiError::PlatformPublicKeyNotFound(serial_no.into()))?,
+            };
+            verify_response_signature(&public_key, &headers, &text)?;
+        }
+
+        let serial_nos = downloaded.keys().cloned().collect();
+        certificates.update(downloaded);
+        Ok(serial_nos)
+    }
+
+    /// 开启自动更新时，按需下载平台证书
+    async fn ensure_platform_certificates(
+        &self,
+        unknown_serial_no: bool,
+    ) -> Result<(), WxpayApiError> {
+        let certificates = self.platform_certificates();
+        if !certificates.auto_refresh || self.apiv3_key().is_err() {
+            return Ok(());
+        }
+        let should_refresh = |certificates: &PlatformCertificates| {
+            certificates.is_refresh_due()
+                || (unknown_serial_no && certificates.can_refresh_for_unknown_serial())
+        };
+        if !should_refresh(certificates) {
+            return Ok(());
+        }
+
+        let _guard = certificates.refresh_lock.lock().await;
+        // 等待锁期间可能已经被其他调用更新过
+        if should_refresh(certificates) {
+            self.refresh_platform_certificates().await?;
+        }
+        Ok(())
+    }
+
+    /// 获取平台证书序列号或微信支付公钥 ID 对应的公钥，未知的平台证书序列号会触发重新下载
+    pub(crate) async fn platform_public_key(
+        &self,
+        serial_no: &str,
+    ) -> Result<RsaPublicKey, WxpayApiError> {
+        let certificates = self.platform_certificates();
+        // 下载平台证书并不能得到微信支付公钥
+        let is_unknown = certificates.get(serial_no).is_none()
+            && !serial_no.starts_with(WXPAY_PUBLIC_KEY_ID_PREFIX);
+        self.ensure_platform_certificates(is_unknown).await?;
+        certificates
+            .get(serial_no)
+            .ok_or_else(|| WxpayApiError::PlatformPublicKeyNotFound(serial_no.into()))
+    }
+
+    /// 用于加密敏感信息以及需要 `Wechatpay-Serial` 请求头的接口的公钥 ID 或平台证书序列号，以及对应的公钥
+    pub(crate) async fn newest_platform_public_key(
+        &self,
+    ) -> Result<(String, RsaPublicKey), WxpayApiError> {
+        self.ensure_platform_certificates(false).await?;
+        self.platform_certificates()
+            .newest()
+            .ok_or_else(|| WxpayApiError::PlatformPublicKeyNotFound(String::new()))
+    }
+}
+
+#[test]
+fn test_extract_public_key_from_certificate() {
+    let certificate = "-----BEGIN CERTIFICATE-----
+MIIDCzCCAfOgAwIBAgIUZkZCTk9TIt6hIL7IDOZH0Mu1q6owDQYJKoZIhvcNAQEL
+BQAwFTETMBEGA1UEAwwKd3hwYXktdGVzdDAeFw0yNjEwMTgwMzUxMTVaFw0zNjEw
+MTUwMzUxMTVaMBUxEzARBgNVBAMMCnd4cGF5LXRlc3QwggEiMA0GCSqGSIb3DQEB
+AQUAA4IBDwAwggEKAoIBAQDOtb4SOuQw5Sw7Wi0+4TbvFkBp9+/d+8/Lom3VlrHE
+iyOOoQCGELCTpF4c1JpFjLfvdb8+LsTULqjXkpzD1bD87SmvknLErLSPpY7XIbIN
+BA8G2mdsUd6AUt2JKeYymEJZUUS2KzbHPNCZ9LGIovih937Uxxg0AcmZG4xUNBfI
+Clz9gc5EFhqERxXMjkSiN1dE9nT08OkA7d+2S3+JrcaKXpGzmnhmouY0EQF7NM/6
+O7pCwCu54Q15oz8jrCNoIuXn1C6npg0e6BrbuAxlllTNI2RmlQeN0FV/QYodCFE7
+zJRHU1mjHdDm0ktliMV2hn8MZT+u9UF/vCc3ReTKzx23AgMBAAGjUzBRMB0GA1Ud
+DgQWBBTHm2ogo/tIALfSnad2rtXgkw0bhTAfBgNVHSMEGDAWgBTHm2ogo/tIALfS
+nad2rtXgkw0bhTAPBgNVHRMBAf8EBTADAQH/MA0GCSqGSIb3DQEBCwUAA4IBAQAT
+8DfJBCBfSxISsbo7JR34kD+sysom43quhVxuGmE79jTf1AVTG5HZDOiU4q7znbyS
+8G95Fc5OC8I0Cv05Ktm6DWasbas0mZ4+pKDMWwfSVdzwGQJrDTLLCRn6jKi4rjEf
+dEda22qaZ0ZNwPFQxYxpzCg9Upvdpel80avNzD53nwNGjU4ORCz+7uDQpHaZmGLR
+ff81ny96PODh09gmRVbUPwIarEYesoP9Ibo31btK3KQG8gM4zKdTLm0/xFZoWQK0
+nQX7OcT8b3lrEiZ/iuEe0DCqt7UvUeet7b0v+aXBsifvmu0DakY/bsV/KRZjPoiD
+hdJAfKbSsH1EAtF0l/9J
+-----END CERTIFICATE-----";
+    let public_key = "-----BEGIN PUBLIC KEY-----
+MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAzrW+EjrkMOUsO1otPuE2
+7xZAaffv3fvPy6Jt1ZaxxIsjjqEAhhCwk6ReHNSaRYy373W/Pi7E1C6o15Kcw9Ww
+/O0pr5JyxKy0j6WO1yGyDQQPBtpnbFHegFLdiSnmMphCWVFEtis2xzzQmfSxiKL4
+ofd+1McYNAHJmRuMVDQXyApc/YHORBYahEcVzI5EojdXRPZ09PDpAO3ftkt/ia3G
+il6Rs5p4ZqLmNBEBezTP+ju6QsArueENeaM/I6wjaCLl59Qup6YNHuga27gMZZZU
+zSNkZpUHjdBVf0GKHQhRO8yUR1NZox3Q5tJLZYjFdoZ/DGU/rvVBf7wnN0Xkys8d
+twIDAQAB
+-----END PUBLIC KEY-----";
+
+    let result = extract_public_key_from_certificate(certificate).unwrap();
+    assert_eq!(
+        result,
+        RsaPublicKey::from_public_key_pem(public_key).unwrap()
+    );
+    assert!(extract_public_key_from_certificate(public_key).is_err());
+}
+
+#[test]
+fn test_select_wxpay_public_key() {
+    use rsa::RsaPrivateKey;
+
+    let new_key =
+        || RsaPublicKey::from(&RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap());
+    let certificate_key = new_key();
+    let wxpay_public_key = new_key();
+    let certificates = PlatformCertificates::new(
+        HashMap::from([(
+            "5157F09EFDC096DE15EBE81A47057A72".to_string(),
+            certificate_key.clone(),
+        )]),
+        Some((
+            "PUB_KEY_ID_0114232134912410000000".to_string(),
+            wxpay_public_key.clone(),
+        )),
+        true,
+    );
+
+    assert_eq!(
+        certificates.get("5157F09EFDC096DE15EBE81A47057A72"),
+        Some(certificate_key)
+    );
+    assert_eq!(
+        certificates.get("PUB_KEY_ID_0114232134912410000000"),
+        Some(wxpay_public_key.clone())
+    );
+    assert_eq!(certificates.get("PUB_KEY_ID_unknown"), None);
+    assert_eq!(
+        certificates.newest(),
+        Some((
+            "PUB_KEY_ID_0114232134912410000000".to_string(),
+            wxpay_public_key
+        ))
+    );
+    assert!(!certificates.is_refresh_due());
+}
+
+#[test]
+fn test_platform_certificates_refresh_state() {
+    use rsa::RsaPrivateKey;
+
+    let new_key =
+        || RsaPublicKey::from(&RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap());
+    let certificates = PlatformCertificates::new(
+        HashMap::from([("MANUAL".to_string(), new_key())]),
+        None,
+        true,
+    );
+    assert!(certificates.is_refresh_due());
+    assert!(certificates.can_refresh_for_unknown_serial());
+
+    // 下载失败时只记录了尝试时间，仍然不会立即重试
+    *certificates.last_attempted_at.lock().unwrap() = Some(Instant::now());
+    assert!(!certificates.is_refresh_due());
+    assert!(!certificates.can_refresh_for_unknown_serial());
+
+    let expired = DateTime::parse_from_rfc3339("2020-01-01T00:00:00+08:00").unwrap();
+    let valid = DateTime::parse_from_rfc3339("2099-01-01T00:00:00+08:00").unwrap();
+    certificates.keys.write().unwrap().insert(
+        "EXPIRED".into(),
+        PlatformPublicKey {
+            public_key: new_key(),
+            expire_time: Some(expired),
+        },
+    );
+    certificates.update(HashMap::from([(
+        "VALID".to_string(),
+        PlatformPublicKey {
+            public_key: new_key(),
+            expire_time: Some(valid),
+        },
+    )]));
+    assert!(certificates.get("EXPIRED").is_none());
+    assert!(certificates.get("VALID").is_some());
+    // 手动配置的公钥没有过期时间，不会被移除
+    assert!(certificates.get("MANUAL").is_some());
+    assert!(!certificates.is_refresh_due());
+}
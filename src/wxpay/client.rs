@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+
+use reqwest::{header::HeaderMap, Method, RequestBuilder};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::api::WXPAY_DEFAULT_BASE_URL;
+use super::callback::verify_wxpay_signature;
+use super::certificate::PlatformCertificates;
+use super::error::{WxPayFailedResponse, WxpayApiError};
+use super::utils::{parse_private_key, parse_public_key, sign_wxpay_request};
+use crate::common::default_http_client;
+
+const USER_AGENT: &str = concat!("wechat-vendor-sdk/", env!("CARGO_PKG_VERSION"));
+
+/// 持有商户配置的微信支付 client，商户私钥只在构造时解析一次
+///
+/// ```ignore
+/// let client = WxpayClient::builder()
+///     .mchid(mchid)
+///     .mch_private_key(mch_private_key)
+///     .mch_serial_no(mch_serial_no)
+///     .apiv3_key(apiv3_key)
+///     .platform_public_keys(HashMap::from([(wxpay_serial_no.to_string(), wx_public_key.to_string())]))
+///     .build()?;
+/// let res_data = client.request_jsapi_order(&body).await?;
+///
+/// // 使用微信支付公钥的商户
+/// let client = WxpayClient::builder()
+///     .mchid(mchid)
+///     .mch_private_key(mch_private_key)
+///     .mch_serial_no(mch_serial_no)
+///     .apiv3_key(apiv3_key)
+///     .wxpay_public_key_id("PUB_KEY_ID_xxx")
+///     .wxpay_public_key(wxpay_public_key)
+///     .build()?;
+/// ```
+pub struct WxpayClient {
+    mchid: String,
+    mch_private_key: RsaPrivateKey,
+    mch_serial_no: String,
+    apiv3_key: Option<String>,
+    platform_certificates: PlatformCertificates,
+    verify_response: bool,
+    base_url: String,
+    http_client: reqwest::Client,
+}
+
+#[bon::bon]
+impl WxpayClient {
+    #[builder]
+    pub fn new(
+        /// 商户号
+        #[builder(into)]
+        mchid: String,
+        /// 商户私钥（pkcs8 pem 格式）
+        mch_private_key: &str,
+        /// 商户证书序列号
+        #[builder(into)]
+        mch_serial_no: String,
+        /// 商户 APIv3 密钥，用于解密回调通知等
+        #[builder(into)]
+        apiv3_key: Option<String>,
+        /// 微信支付平台证书序列号 -> 平台证书公钥（pem 格式），用于验证回调签名、加密敏感信息等
+        #[builder(default)]
+        platform_public_keys: HashMap<String, String>,
+        /// 微信支付公钥 ID，以 `PUB_KEY_ID_` 开头，需要和 `wxpay_public_key` 一起配置
+        #[builder(into)]
+        wxpay_public_key_id: Option<String>,
+        /// 微信支付公钥（pem 格式），使用微信支付公钥的商户用它代替平台证书验证签名、加密敏感信息
+        wxpay_public_key: Option<&str>,
+        /// 配置了 APIv3 密钥时，是否自动下载并定期更新平台证书，默认开启
+        #[builder(default = true)]
+        auto_refresh_certificates: bool,
+        /// 是否使用平台证书或微信支付公钥验证 api 响应的签名，默认开启，可在测试中关闭
+        #[builder(default = true)]
+        verify_response: bool,
+        /// 请求的协议和域名，不以 `/` 结尾，默认为 [`WXPAY_DEFAULT_BASE_URL`]，可在测试中指向本地的 mock 服务
+        #[builder(into, default = WXPAY_DEFAULT_BASE_URL.to_string())]
+        base_url: String,
+        /// 发起请求所用的 http client，默认使用进程内共享的 client
+        #[builder(default = default_http_client())]
+        http_client: reqwest::Client,
+    ) -> Result<Self, WxpayApiError> {
+        let platform_public_keys = platform_public_keys
+            .iter()
+            .map(|(serial_no, public_key)| Ok((serial_no.clone(), parse_public_key(public_key)?)))
+            .collect::<Result<_, WxpayApiError>>()?;
+        let wxpay_public_key = match (wxpay_public_key_id, wxpay_public_key) {
+            (Some(public_key_id), Some(public_key)) => {
+                Some((public_key_id, parse_public_key(public_key)?))
+            }
+            (None, None) => None,
+            _ => return Err(WxpayApiError::InvalidPublicKey),
+        };
+        Ok(Self {
+            mchid,
+            mch_private_key: parse_private_key(mch_private_key)?,
+            mch_serial_no,
+            apiv3_key,
+            platform_certificates: PlatformCertificates::new(
+                platform_public_keys,
+                wxpay_public_key,
+                auto_refresh_certificates,
+            ),
+            verify_response,
+            base_url,
+            http_client,
+        })
+    }
+}
+
+impl WxpayClient {
+    pub fn mchid(&self) -> &str {
+        &self.mchid
+    }
+
+    pub(crate) fn mch_private_key(&self) -> &RsaPrivateKey {
+        &self.mch_private_key
+    }
+
+    pub(crate) fn apiv3_key(&self) -> Result<&str, WxpayApiError> {
+        self.apiv3_key
+            .as_deref()
+            .ok_or(WxpayApiError::MissingApiv3Key)
+    }
+
+    pub(crate) fn platform_certificates(&self) -> &PlatformCertificates {
+        &self.platform_certificates
+    }
+
+    pub(crate) fn verify_response_enabled(&self) -> bool {
+        self.verify_response
+    }
+
+    /// 对请求签名并发送，响应状态码不为 2xx 时返回 [`WxpayApiError::WxpayError`]，否则验证响应签名后返回响应体
+    ///
+    /// * `url_path` - 包含 query 的请求路径，如 `/v3/pay/transactions/out-trade-no/xx?mchid=xx`
+    /// * `wechatpay_serial` - 请求中包含敏感信息加密字段时，需要传入加密所用的平台证书序列号或微信支付公钥 ID
+    pub(crate) async fn send(
+        &self,
+        method: Method,
+        url_path: &str,
+        body: Option<String>,
+        wechatpay_serial: Option<&str>,
+    ) -> Result<String, WxpayApiError> {
+        let (headers, text) = self
+            .send_without_verify(method, url_path, body, wechatpay_serial)
+            .await?;
+        self.verify_response(&headers, &text).await?;
+        Ok(text)
+    }
+
+    /// 开启了响应验签时，使用响应头 `Wechatpay-Serial` 对应的平台证书或微信支付公钥验证响应签名
+    pub(crate) async fn verify_response(
+        &self,
+        headers: &HeaderMap,
+        body: &str,
+    ) -> Result<(), WxpayApiError> {
+        if self.verify_response {
+            let serial_no = response_header(headers, "Wechatpay-Serial")?;
+            let public_key = self.platform_public_key(serial_no).await?;
+            verify_response_signature(&public_key, headers, body)?;
+        }
+        Ok(())
+    }
+
+    /// 构造带有签名的请求，`signed_body` 为参与签名的请求体，请求体和 `Content-Type` 由调用方设置
+    pub(crate) fn signed_request(
+        &self,
+        method: Method,
+        url_path: &str,
+        signed_body: Option<&str>,
+    ) -> RequestBuilder {
+        let (signature, timestamp, nonce_str) = sign_wxpay_request(
+            method.as_str(),
+            url_path,
+            &self.mch_private_key,
+            signed_body,
+        );
+
+        self.http_client
+            .request(method, format!("{}{}", self.base_url, url_path))
+            .header("User-Agent", USER_AGENT)
+            .header("Authorization", format!("WECHATPAY2-SHA256-RSA2048 mchid=\"{}\",nonce_str=\"{}\",signature=\"{}\",timestamp=\"{}\",serial_no=\"{}\"",
+                self.mchid, nonce_str, signature, timestamp, self.mch_serial_no))
+    }
+
+    /// 对请求签名并发送，不验证响应签名，返回响应头和响应体
+    pub(crate) async fn send_without_verify(
+        &self,
+        method: Method,
+        url_path: &str,
+        body: Option<String>,
+        wechatpay_serial: Option<&str>,
+    ) -> Result<(HeaderMap, String), WxpayApiError> {
+        let mut request = self
+            .signed_request(method, url_path, body.as_deref())
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json");
+        if let Some(wechatpay_serial) = wechatpay_serial {
+            request = request.header("Wechatpay-Serial", wechatpay_serial);
+        }
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+        self.execute(request).await
+    }
+
+    /// 发送已签名的请求，响应状态码不为 2xx 时返回 [`WxpayApiError::WxpayError`]，不验证响应签名
+    pub(crate) async fn execute(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<(HeaderMap, String), WxpayApiError> {
+        let response = request.send().await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let text = response.text().await?;
+        if status.is_success() {
+            Ok((headers, text))
+        } else {
+            let result: WxPayFailedResponse = serde_json::from_str(&text)?;
+            Err(WxpayApiError::WxpayError(result))
+        }
+    }
+
+    pub(crate) async fn get<D>(&self, url_path: &str) -> Result<D, WxpayApiError>
+    where
+        D: DeserializeOwned,
+    {
+        let text = self.send(Method::GET, url_path, None, None).await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub(crate) async fn post<B, D>(&self, url_path: &str, body: &B) -> Result<D, WxpayApiError>
+    where
+        B: Serialize,
+        D: DeserializeOwned,
+    {
+        let body = serde_json::to_string(body)?;
+        let text = self.send(Method::POST, url_path, Some(body), None).await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// 用于成功时响应 204 无响应体的接口
+    pub(crate) async fn post_without_response<B>(
+        &self,
+        url_path: &str,
+        body: &B,
+    ) -> Result<(), WxpayApiError>
+    where
+        B: Serialize,
+    {
+        let body = serde_json::to_string(body)?;
+        self.send(Method::POST, url_path, Some(body), None).await?;
+        Ok(())
+    }
+}
+
+fn response_header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, WxpayApiError> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| WxpayApiError::InvalidResponseSignature(format!("missing header {}", name)))
+}
+
+/// 使用 `Wechatpay-Timestamp`、`Wechatpay-Nonce`、`Wechatpay-Signature` 响应头验证响应签名
+pub(crate) fn verify_response_signature(
+    public_key: &RsaPublicKey,
+    headers: &HeaderMap,
+    body: &str,
+) -> Result<(), WxpayApiError> {
+    let verified = verify_wxpay_signature(
+        public_key,
+        response_header(headers, "Wechatpay-Signature")?,
+        response_header(headers, "Wechatpay-Timestamp")?,
+        response_header(headers, "Wechatpay-Nonce")?,
+        body,
+        false,
+    )?;
+    if verified {
+        Ok(())
+    } else {
+        Err(WxpayApiError::InvalidResponseSignature(
+            "signature mismatch".into(),
+        ))
+    }
+}
+
+#[test]
+fn test_verify_response_signature() {
+    use super::utils::{generate_noncestr, sign_sha256_rsa};
+
+    let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+    let public_key = RsaPublicKey::from(&private_key);
+    let timestamp = chrono::Utc::now().timestamp().to_string();
+    let nonce = generate_noncestr(32);
+    let body = r#"{"code_url":"weixin://wxpay/bizpayurl?pr=p4lpSuKzz"}"#;
+    let signature = sign_sha256_rsa(
+        &private_key,
+        &format!("{}\n{}\n{}\n", timestamp, nonce, body),
+    );
+
+    let mut headers = HeaderMap::new();
+    headers.insert("Wechatpay-Timestamp", timestamp.parse().unwrap());
+    headers.insert("Wechatpay-Nonce", nonce.parse().unwrap());
+    headers.insert("Wechatpay-Signature", signature.parse().unwrap());
+
+    assert!(verify_response_signature(&public_key, &headers, body).is_ok());
+    assert!(matches!(
+        verify_response_signature(&public_key, &headers, "{}"),
+        Err(WxpayApiError::InvalidResponseSignature(_))
+    ));
+    headers.remove("Wechatpay-Nonce");
+    assert!(matches!(
+        verify_response_signature(&public_key, &headers, body),
+        Err(WxpayApiError::InvalidResponseSignature(_))
+    ));
+}
@@ -0,0 +1,336 @@
+//! [合单支付](https://pay.weixin.qq.com/doc/v3/merchant/4012556524)
+//!
+//! 一次支付多个子单（最多 50 个），各子单可以属于不同的商户。JSAPI/APP 合单下单返回的 `prepay_id`
+//! 和普通下单一致，调起支付的参数使用 [`WxpayClient::jsapi_pay_params`] 或 [`WxpayClient::app_pay_params`] 生成。
+//! 合单支付成功的回调通知解析为 [`WxpayCallbackEvent::CombineTransactionSuccess`](super::callback::WxpayCallbackEvent::CombineTransactionSuccess)。
+//!
+//! 服务商模式的合单支付使用同样的接口，子单的 `mchid` 为服务商商户号，并填写 `sub_mchid`（和 `sub_appid`）。
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    api::{
+        temporary_client, AppOrderResponseData, H5OrderResponseData, JsapiOrderResponseData,
+        NativeOrderResponseData, OrderSceneInfo, Payer, SceneInfo, SettleInfo, TradeState,
+        WXPAY_DEFAULT_BASE_URL,
+    },
+    client::WxpayClient,
+    error::WxpayApiError,
+};
+
+#[derive(Debug, Serialize)]
+pub struct CombineOrderRequestBody<'a> {
+    /// 合单发起方的 appid
+    pub combine_appid: &'a str,
+    /// 合单发起方商户号
+    pub combine_mchid: &'a str,
+    pub combine_out_trade_no: &'a str,
+    /// H5 合单下单必须包含 `payer_client_ip` 和 `h5_info`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scene_info: Option<OrderSceneInfo<'a>>,
+    pub sub_orders: Vec<CombineSubOrder<'a>>,
+    /// JSAPI 合单下单时必填
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub combine_payer_info: Option<CombinePayerInfo<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_start: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_expire: Option<&'a str>,
+    pub notify_url: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CombineSubOrder<'a> {
+    /// 子单商户号，服务商模式为服务商商户号
+    pub mchid: &'a str,
+    /// 服务商模式的子商户号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_mchid: Option<&'a str>,
+    /// 服务商模式的子商户 appid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_appid: Option<&'a str>,
+    /// 附加数据，在查询和回调中原样返回
+    pub attach: &'a str,
+    pub amount: CombineOrderAmount<'a>,
+    pub out_trade_no: &'a str,
+    pub description: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_tag: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settle_info: Option<SettleInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CombineOrderAmount<'a> {
+    /// 子单金额，单位为分
+    pub total_amount: i64,
+    /// 目前只支持 `CNY`
+    pub currency: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CombinePayerInfo<'a> {
+    pub openid: &'a str,
+}
+
+/// 合单查询和合单支付成功回调返回的数据
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CombineOrderDetailResponseData {
+    pub combine_appid: String,
+    pub combine_mchid: String,
+    pub combine_out_trade_no: String,
+    pub scene_info: Option<SceneInfo>,
+    pub sub_orders: Vec<CombineSubOrderDetail>,
+    pub combine_payer_info: Option<Payer>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CombineSubOrderDetail {
+    pub mchid: String,
+    pub trade_type: Option<String>,
+    pub trade_state: TradeState,
+    pub bank_type: Option<String>,
+    pub attach: Option<String>,
+    pub success_time: Option<DateTime<FixedOffset>>,
+    pub transaction_id: Option<String>,
+    pub out_trade_no: String,
+    pub sub_mchid: Option<String>,
+    pub amount: CombineSubOrderDetailAmount,
+    pub promotion_detail: Option<Vec<Value>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CombineSubOrderDetailAmount {
+    pub total_amount: i64,
+    pub currency: Option<String>,
+    /// 用户实际支付金额，单位为分
+    pub payer_amount: Option<i64>,
+    pub payer_currency: Option<String>,
+}
+
+/// [合单支付成功回调通知](https://pay.weixin.qq.com/doc/v3/merchant/4012556532)解密后的数据（和合单查询返回格式一致）
+pub type CombineCallbackResourceData = CombineOrderDetailResponseData;
+
+#[derive(Debug, Serialize)]
+pub struct CloseCombineOrderRequestBody<'a> {
+    pub combine_appid: &'a str,
+    /// 需要关闭的子单，合单内的子单需要全部关闭
+    pub sub_orders: Vec<CloseCombineSubOrder<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CloseCombineSubOrder<'a> {
+    pub mchid: &'a str,
+    pub out_trade_no: &'a str,
+    /// 服务商模式的子商户号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_mchid: Option<&'a str>,
+    /// 服务商模式的子商户 appid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_appid: Option<&'a str>,
+}
+
+/// [JSAPI合单下单](https://pay.weixin.qq.com/doc/v3/merchant/4012556536)
+#[bon::builder]
+pub async fn request_combine_jsapi_order<'a>(
+    body: CombineOrderRequestBody<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<JsapiOrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_combine_jsapi_order(&body).await
+}
+
+/// [APP合单下单](https://pay.weixin.qq.com/doc/v3/merchant/4012556538)
+#[bon::builder]
+pub async fn request_combine_app_order<'a>(
+    body: CombineOrderRequestBody<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<AppOrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_combine_app_order(&body).await
+}
+
+/// [H5合单下单](https://pay.weixin.qq.com/doc/v3/merchant/4012556541)
+#[bon::builder]
+pub async fn request_combine_h5_order<'a>(
+    body: CombineOrderRequestBody<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<H5OrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_combine_h5_order(&body).await
+}
+
+/// [Native合单下单](https://pay.weixin.qq.com/doc/v3/merchant/4012556543)
+#[bon::builder]
+pub async fn request_combine_native_order<'a>(
+    body: CombineOrderRequestBody<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<NativeOrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_combine_native_order(&body).await
+}
+
+/// [合单查询订单](https://pay.weixin.qq.com/doc/v3/merchant/4012556546)
+#[bon::builder]
+pub async fn request_combine_order_detail<'a>(
+    combine_out_trade_no: &'a str,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<CombineOrderDetailResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .request_combine_order_detail(combine_out_trade_no)
+        .await
+}
+
+/// [合单关闭订单](https://pay.weixin.qq.com/doc/v3/merchant/4012556547)
+#[bon::builder]
+pub async fn request_close_combine_order<'a>(
+    body: CloseCombineOrderRequestBody<'a>,
+    combine_out_trade_no: &'a str,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<(), WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .request_close_combine_order(combine_out_trade_no, &body)
+        .await
+}
+
+impl WxpayClient {
+    /// [JSAPI合单下单](https://pay.weixin.qq.com/doc/v3/merchant/4012556536)，`combine_payer_info` 必填
+    pub async fn request_combine_jsapi_order(
+        &self,
+        body: &CombineOrderRequestBody<'_>,
+    ) -> Result<JsapiOrderResponseData, WxpayApiError> {
+        self.post("/v3/combine-transactions/jsapi", body).await
+    }
+
+    /// [APP合单下单](https://pay.weixin.qq.com/doc/v3/merchant/4012556538)
+    pub async fn request_combine_app_order(
+        &self,
+        body: &CombineOrderRequestBody<'_>,
+    ) -> Result<AppOrderResponseData, WxpayApiError> {
+        self.post("/v3/combine-transactions/app", body).await
+    }
+
+    /// [H5合单下单](https://pay.weixin.qq.com/doc/v3/merchant/4012556541)，`scene_info.h5_info` 必填
+    pub async fn request_combine_h5_order(
+        &self,
+        body: &CombineOrderRequestBody<'_>,
+    ) -> Result<H5OrderResponseData, WxpayApiError> {
+        self.post("/v3/combine-transactions/h5", body).await
+    }
+
+    /// [Native合单下单](https://pay.weixin.qq.com/doc/v3/merchant/4012556543)
+    pub async fn request_combine_native_order(
+        &self,
+        body: &CombineOrderRequestBody<'_>,
+    ) -> Result<NativeOrderResponseData, WxpayApiError> {
+        self.post("/v3/combine-transactions/native", body).await
+    }
+
+    /// [合单查询订单](https://pay.weixin.qq.com/doc/v3/merchant/4012556546)
+    pub async fn request_combine_order_detail(
+        &self,
+        combine_out_trade_no: &str,
+    ) -> Result<CombineOrderDetailResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/combine-transactions/out-trade-no/{}",
+            combine_out_trade_no
+        ))
+        .await
+    }
+
+    /// [合单关闭订单](https://pay.weixin.qq.com/doc/v3/merchant/4012556547)
+    pub async fn request_close_combine_order(
+        &self,
+        combine_out_trade_no: &str,
+        body: &CloseCombineOrderRequestBody<'_>,
+    ) -> Result<(), WxpayApiError> {
+        self.post_without_response(
+            &format!(
+                "/v3/combine-transactions/out-trade-no/{}/close",
+                combine_out_trade_no
+            ),
+            body,
+        )
+        .await
+    }
+}
+
+#[test]
+fn test_deserialize_combine_order_detail() {
+    let data: CombineOrderDetailResponseData = serde_json::from_str(
+        r#"{"combine_appid":"wxd678efh567hg6787","combine_mchid":"1900000109","combine_out_trade_no":"P20150806125346","scene_info":{"device_id":"POS1:1"},"sub_orders":[{"mchid":"1900000109","trade_type":"JSAPI","trade_state":"SUCCESS","bank_type":"CMC","attach":"深圳分店","success_time":"2015-05-20T13:29:35+08:00","transaction_id":"1009660380201506130728806387","out_trade_no":"20150806125346","amount":{"total_amount":10,"currency":"CNY","payer_amount":10,"payer_currency":"CNY"}},{"mchid":"1900000110","trade_state":"NOTPAY","attach":"深圳分店","out_trade_no":"20150806125347","amount":{"total_amount":20}}],"combine_payer_info":{"openid":"oUpF8uMuAJO_M2pxb1Q9zNjWeS6o"}}"#,
+    )
+    .unwrap();
+    assert_eq!(data.sub_orders.len(), 2);
+    assert_eq!(data.sub_orders[0].trade_state, TradeState::Success);
+    assert_eq!(data.sub_orders[0].amount.payer_amount, Some(10));
+    assert_eq!(data.sub_orders[1].trade_state, TradeState::NotPay);
+}
+
+#[test]
+fn test_serialize_partner_combine_sub_order() {
+    let sub_order = CombineSubOrder {
+        mchid: "1230000109",
+        sub_mchid: Some("1900000109"),
+        sub_appid: None,
+        attach: "深圳分店",
+        amount: CombineOrderAmount {
+            total_amount: 10,
+            currency: "CNY",
+        },
+        out_trade_no: "20150806125346",
+        description: "腾讯充值中心-QQ会员充值",
+        goods_tag: None,
+        settle_info: None,
+    };
+    assert_eq!(
+        serde_json::to_value(&sub_order).unwrap(),
+        serde_json::json!({
+            "mchid": "1230000109",
+            "sub_mchid": "1900000109",
+            "attach": "深圳分店",
+            "amount": {"total_amount": 10, "currency": "CNY"},
+            "out_trade_no": "20150806125346",
+            "description": "腾讯充值中心-QQ会员充值",
+        })
+    );
+}
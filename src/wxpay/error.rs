@@ -19,12 +19,18 @@ pub enum WxpayApiError {
     #[error("invalid public key")]
     InvalidPublicKey,
 
+    #[error("invalid certificate")]
+    InvalidCertificate,
+
     #[error("invalid private key")]
     InvalidPrivateKey,
 
     #[error("decrypt failed")]
     DecryptFailed,
 
+    #[error("encrypt failed")]
+    EncryptFailed,
+
     #[error("request error: {0}")]
     RequestErr(#[from] reqwest::Error),
 
@@ -33,4 +39,32 @@ pub enum WxpayApiError {
 
     #[error("wxpay error: {}", .0.message)]
     WxpayError(WxPayFailedResponse),
+
+    #[error("json error: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("apiv3 key is not configured")]
+    MissingApiv3Key,
+
+    #[error("platform public key not found: {0}")]
+    PlatformPublicKeyNotFound(String),
+
+    #[error("invalid response signature: {0}")]
+    InvalidResponseSignature(String),
+
+    #[error("invalid callback signature: {0}")]
+    InvalidCallbackSignature(String),
+
+    #[error("bill error: {0}")]
+    BillError(String),
+
+    #[error("bill hash mismatch")]
+    BillHashMismatch,
+
+    #[error("invalid media filename: {0}")]
+    InvalidMediaFilename(String),
+
+    #[cfg(feature = "wxpay-qrcode")]
+    #[error("qrcode error: {0}")]
+    QrCodeError(String),
 }
@@ -0,0 +1,181 @@
+//! [图片上传](https://pay.weixin.qq.com/doc/v3/merchant/4012761082)和[视频上传](https://pay.weixin.qq.com/doc/v3/merchant/4012761085)
+//!
+//! 上传接口使用 `multipart/form-data` 请求，签名时只使用 `meta` 部分的 json（文件名和文件的 sha256），
+//! 返回的 `media_id` 用于投诉回复、营销、进件等接口：
+//!
+//! ```ignore
+//! let content = std::fs::read("proof.png")?;
+//! let res_data = client.upload_image("proof.png", &content).await?;
+//! println!("{}", res_data.media_id);
+//! ```
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{client::WxpayClient, error::WxpayApiError, utils::generate_noncestr};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MediaUploadResponseData {
+    /// 媒体文件标识 id
+    pub media_id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct MediaMeta<'a> {
+    filename: &'a str,
+    sha256: String,
+}
+
+/// 生成上传文件的 `meta` json，也是上传请求中参与签名的请求体，可以配合
+/// [`generate_wxpay_request_signature`](super::utils::generate_wxpay_request_signature) 自行发起上传请求
+pub fn generate_wxpay_media_meta(filename: &str, content: &[u8]) -> String {
+    let meta = MediaMeta {
+        filename,
+        sha256: format!("{:x}", Sha256::digest(content)),
+    };
+    serde_json::to_string(&meta).unwrap()
+}
+
+/// 按文件扩展名推断文件的 `Content-Type`
+fn media_content_type(filename: &str) -> &'static str {
+    let extension = filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "bmp" => "image/bmp",
+        "avi" => "video/x-msvideo",
+        "wmv" => "video/x-ms-wmv",
+        "mpeg" => "video/mpeg",
+        "mp4" => "video/mp4",
+        "mov" => "video/quicktime",
+        "mkv" => "video/x-matroska",
+        "flv" => "video/x-flv",
+        "f4v" => "video/x-f4v",
+        "m4v" => "video/x-m4v",
+        "rmvb" => "application/vnd.rn-realmedia-vbr",
+        _ => "application/octet-stream",
+    }
+}
+
+/// 文件名会原样写入 `Content-Disposition`，不允许包含引号、反斜杠和控制字符，
+/// 以免改写 multipart 头部，也保证和 `meta` json 中转义后的文件名一致
+fn check_media_filename(filename: &str) -> Result<(), WxpayApiError> {
+    if filename.is_empty()
+        || filename
+            .chars()
+            .any(|c| c == '"' || c == '\\' || c.is_control())
+    {
+        return Err(WxpayApiError::InvalidMediaFilename(filename.into()));
+    }
+    Ok(())
+}
+
+/// 构造包含 `meta` 和 `file` 两部分的 multipart 请求体
+fn multipart_body(boundary: &str, meta: &str, filename: &str, content: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(content.len() + meta.len() + 256);
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"meta\"\r\nContent-Type: application/json\r\n\r\n{}\r\n",
+            boundary, meta
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+            boundary,
+            filename,
+            media_content_type(filename)
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(content);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+impl WxpayClient {
+    /// [图片上传](https://pay.weixin.qq.com/doc/v3/merchant/4012761082)，支持 JPG、BMP、PNG 格式，文件大小不能超过 2M
+    pub async fn upload_image(
+        &self,
+        filename: &str,
+        content: &[u8],
+    ) -> Result<MediaUploadResponseData, WxpayApiError> {
+        self.upload_media("/v3/merchant/media/upload", filename, content)
+            .await
+    }
+
+    /// [视频上传](https://pay.weixin.qq.com/doc/v3/merchant/4012761085)，支持 avi、wmv、mpeg、mp4、mov、mkv、flv、f4v、m4v、rmvb 格式，文件大小不能超过 5M
+    pub async fn upload_video(
+        &self,
+        filename: &str,
+        content: &[u8],
+    ) -> Result<MediaUploadResponseData, WxpayApiError> {
+        self.upload_media("/v3/merchant/media/video_upload", filename, content)
+            .await
+    }
+
+    async fn upload_media(
+        &self,
+        url_path: &str,
+        filename: &str,
+        content: &[u8],
+    ) -> Result<MediaUploadResponseData, WxpayApiError> {
+        check_media_filename(filename)?;
+        let meta = generate_wxpay_media_meta(filename, content);
+        let boundary = generate_noncestr(32);
+        let request = self
+            .signed_request(Method::POST, url_path, Some(&meta))
+            .header(
+                "Content-Type",
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .header("Accept", "application/json")
+            .body(multipart_body(&boundary, &meta, filename, content));
+        let (headers, text) = self.execute(request).await?;
+        self.verify_response(&headers, &text).await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+#[test]
+fn test_multipart_body() {
+    let meta = generate_wxpay_media_meta("hello.PNG", b"hello");
+    assert_eq!(
+        meta,
+        r#"{"filename":"hello.PNG","sha256":"2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"}"#
+    );
+
+    let body = multipart_body("boundary", &meta, "hello.PNG", b"hello");
+    assert_eq!(
+        String::from_utf8(body).unwrap(),
+        format!(
+            "--boundary\r\nContent-Disposition: form-data; name=\"meta\"\r\nContent-Type: application/json\r\n\r\n{}\r\n\
+             --boundary\r\nContent-Disposition: form-data; name=\"file\"; filename=\"hello.PNG\"\r\nContent-Type: image/png\r\n\r\n\
+             hello\r\n--boundary--\r\n",
+            meta
+        )
+    );
+}
+
+#[test]
+fn test_check_media_filename() {
+    assert!(check_media_filename("hello.PNG").is_ok());
+    assert!(check_media_filename("凭证 1.jpg").is_ok());
+    for filename in [
+        "",
+        "a\".png",
+        "a.png\"; name=\"meta",
+        "a.png\r\nContent-Type: text/html",
+        "a\nb.png",
+        "a\\b.png",
+    ] {
+        assert!(matches!(
+            check_media_filename(filename),
+            Err(WxpayApiError::InvalidMediaFilename(_))
+        ));
+    }
+}
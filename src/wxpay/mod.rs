@@ -1,4 +1,18 @@
 pub mod api;
+pub mod bill;
 pub mod callback;
+pub mod certificate;
+mod client;
+pub use client::WxpayClient;
+pub mod combine;
 pub mod error;
+pub mod media;
+pub mod partner;
+pub mod profitsharing;
+#[cfg(feature = "wxpay-qrcode")]
+#[cfg_attr(docsrs, doc(cfg(feature = "wxpay-qrcode")))]
+pub mod qrcode;
+pub mod reconcile;
+pub mod sensitive;
+pub mod transfer;
 pub mod utils;
@@ -0,0 +1,449 @@
+//! [服务商模式](https://pay.weixin.qq.com/doc/v3/partner/4012069852)
+//!
+//! 服务商代特约商户（子商户）下单、查询、关单和退款。[`WxpayClient`] 使用服务商的商户号、私钥和证书构造，
+//! 请求签名和直连模式一致，`sp_mchid` 即 [`WxpayClient::mchid`]：
+//!
+//! ```ignore
+//! let res_data = client
+//!     .request_partner_jsapi_order(&PartnerOrderRequestBody {
+//!         sp_appid,
+//!         sp_mchid: client.mchid(),
+//!         sub_mchid,
+//!         ..
+//!     })
+//!     .await?;
+//! // 使用 `sp_openid` 下单时调起支付的 appId 为 `sp_appid`，使用 `sub_openid` 下单时为 `sub_appid`
+//! let params = client.jsapi_pay_params(sp_appid, &res_data);
+//! ```
+//!
+//! 和 [`api`](super::api) 一样，每个接口也提供了独立的请求函数，其中的 `mchid` 为服务商商户号。
+//! 服务商模式的回调通知解析为 [`WxpayCallbackEvent`](super::callback::WxpayCallbackEvent) 中以 `Partner` 开头的类型。
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    api::{
+        temporary_client, AppOrderResponseData, H5OrderResponseData, JsapiOrderAmount,
+        JsapiOrderResponseData, NativeOrderResponseData, OrderSceneInfo, RefundOrderRequestBody,
+        RefundOrderResponseData, RefundStatus, SceneInfo, SettleInfo, TradeState,
+        WXPAY_DEFAULT_BASE_URL,
+    },
+    callback::RefundCallbackAmount,
+    client::WxpayClient,
+    error::WxpayApiError,
+};
+
+#[derive(Debug, Serialize)]
+pub struct PartnerOrderRequestBody<'a> {
+    /// 服务商的 appid
+    pub sp_appid: &'a str,
+    /// 服务商商户号
+    pub sp_mchid: &'a str,
+    /// 子商户的 appid，使用 `sub_openid` 下单时必填
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_appid: Option<&'a str>,
+    /// 子商户号
+    pub sub_mchid: &'a str,
+    pub description: &'a str,
+    pub out_trade_no: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_expire: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attach: Option<&'a str>,
+    pub notify_url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goods_tag: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub support_fapiao: Option<bool>,
+    pub amount: JsapiOrderAmount<'a>,
+    /// JSAPI 下单时必填
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payer: Option<PartnerOrderPayer<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<Value>,
+    /// H5 下单必须包含 `payer_client_ip` 和 `h5_info`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scene_info: Option<OrderSceneInfo<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settle_info: Option<SettleInfo>,
+}
+
+/// 服务商模式的支付者，`sp_openid` 和 `sub_openid` 二选一
+#[derive(Debug, Serialize)]
+pub struct PartnerOrderPayer<'a> {
+    /// 用户在服务商 appid 下的 openid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sp_openid: Option<&'a str>,
+    /// 用户在子商户 appid 下的 openid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_openid: Option<&'a str>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PartnerOrderDetailResponseData {
+    pub sp_appid: String,
+    pub sp_mchid: String,
+    pub sub_appid: Option<String>,
+    pub sub_mchid: String,
+    pub out_trade_no: String,
+    pub transaction_id: Option<String>,
+    pub trade_type: Option<String>,
+    pub trade_state: TradeState,
+    pub trade_state_desc: String,
+    pub bank_type: Option<String>,
+    pub attach: Option<String>,
+    pub success_time: Option<DateTime<FixedOffset>>,
+    pub payer: Option<PartnerPayer>,
+    pub amount: Option<PartnerAmount>,
+    pub scene_info: Option<SceneInfo>,
+    pub promotion_detail: Option<Vec<Value>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PartnerPayer {
+    pub sp_openid: Option<String>,
+    pub sub_openid: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PartnerAmount {
+    pub total: Option<i32>,
+    pub payer_total: Option<i32>,
+    pub currency: Option<String>,
+    pub payer_currency: Option<String>,
+}
+
+/// [服务商模式支付成功回调通知](https://pay.weixin.qq.com/doc/v3/partner/4012085801)解密后的数据（和查询订单返回格式一致）
+pub type PartnerPayCallbackResourceData = PartnerOrderDetailResponseData;
+
+/// [服务商模式退款结果回调通知](https://pay.weixin.qq.com/doc/v3/partner/4012085962)解密后的数据
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PartnerRefundCallbackResourceData {
+    pub sp_mchid: String,
+    pub sub_mchid: String,
+    pub transaction_id: String,
+    pub out_trade_no: String,
+    pub refund_id: String,
+    pub out_refund_no: String,
+    pub refund_status: RefundStatus,
+    pub success_time: Option<DateTime<FixedOffset>>,
+    pub user_received_account: String,
+    pub amount: RefundCallbackAmount,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PartnerCloseOrderRequestBody<'a> {
+    pub sp_mchid: &'a str,
+    pub sub_mchid: &'a str,
+}
+
+/// 服务商模式的退款申请，在直连模式的请求体上增加子商户号
+#[derive(Debug, Serialize)]
+pub struct PartnerRefundOrderRequestBody<'a> {
+    pub sub_mchid: &'a str,
+    #[serde(flatten)]
+    pub refund: RefundOrderRequestBody<'a>,
+}
+
+/// [服务商模式JSAPI/小程序下单](https://pay.weixin.qq.com/doc/v3/partner/4012085752)
+#[bon::builder]
+pub async fn request_partner_jsapi_order<'a>(
+    body: PartnerOrderRequestBody<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<JsapiOrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_partner_jsapi_order(&body).await
+}
+
+/// [服务商模式APP下单](https://pay.weixin.qq.com/doc/v3/partner/4012085768)
+#[bon::builder]
+pub async fn request_partner_app_order<'a>(
+    body: PartnerOrderRequestBody<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<AppOrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_partner_app_order(&body).await
+}
+
+/// [服务商模式H5下单](https://pay.weixin.qq.com/doc/v3/partner/4012085776)
+#[bon::builder]
+pub async fn request_partner_h5_order<'a>(
+    body: PartnerOrderRequestBody<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<H5OrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_partner_h5_order(&body).await
+}
+
+/// [服务商模式Native下单](https://pay.weixin.qq.com/doc/v3/partner/4012085784)
+#[bon::builder]
+pub async fn request_partner_native_order<'a>(
+    body: PartnerOrderRequestBody<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<NativeOrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_partner_native_order(&body).await
+}
+
+/// [服务商模式商户订单号查询订单](https://pay.weixin.qq.com/doc/v3/partner/4012085791)
+#[bon::builder]
+pub async fn request_partner_order_detail_by_out_trade_no<'a>(
+    sub_mchid: &'a str,
+    out_trade_no: &'a str,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<PartnerOrderDetailResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .request_partner_order_detail_by_out_trade_no(sub_mchid, out_trade_no)
+        .await
+}
+
+/// [服务商模式微信支付订单号查询订单](https://pay.weixin.qq.com/doc/v3/partner/4012085790)
+#[bon::builder]
+pub async fn request_partner_order_detail_by_transaction_id<'a>(
+    sub_mchid: &'a str,
+    transaction_id: &'a str,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<PartnerOrderDetailResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .request_partner_order_detail_by_transaction_id(sub_mchid, transaction_id)
+        .await
+}
+
+/// [服务商模式关闭订单](https://pay.weixin.qq.com/doc/v3/partner/4012085794)
+#[bon::builder]
+pub async fn request_partner_close_order<'a>(
+    sub_mchid: &'a str,
+    out_trade_no: &'a str,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<(), WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .request_partner_close_order(sub_mchid, out_trade_no)
+        .await
+}
+
+/// [服务商模式退款申请](https://pay.weixin.qq.com/doc/v3/partner/4012085949)
+#[bon::builder]
+pub async fn request_partner_refund_order<'a>(
+    body: PartnerRefundOrderRequestBody<'a>,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<RefundOrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_partner_refund_order(&body).await
+}
+
+/// [服务商模式查询单笔退款](https://pay.weixin.qq.com/doc/v3/partner/4012085958)
+#[bon::builder]
+pub async fn request_partner_refund_detail<'a>(
+    sub_mchid: &'a str,
+    out_refund_no: &'a str,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<RefundOrderResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .request_partner_refund_detail(sub_mchid, out_refund_no)
+        .await
+}
+
+impl WxpayClient {
+    /// [服务商模式JSAPI/小程序下单](https://pay.weixin.qq.com/doc/v3/partner/4012085752)，`payer` 必填
+    pub async fn request_partner_jsapi_order(
+        &self,
+        body: &PartnerOrderRequestBody<'_>,
+    ) -> Result<JsapiOrderResponseData, WxpayApiError> {
+        self.post("/v3/pay/partner/transactions/jsapi", body).await
+    }
+
+    /// [服务商模式APP下单](https://pay.weixin.qq.com/doc/v3/partner/4012085768)
+    pub async fn request_partner_app_order(
+        &self,
+        body: &PartnerOrderRequestBody<'_>,
+    ) -> Result<AppOrderResponseData, WxpayApiError> {
+        self.post("/v3/pay/partner/transactions/app", body).await
+    }
+
+    /// [服务商模式H5下单](https://pay.weixin.qq.com/doc/v3/partner/4012085776)，`scene_info.h5_info` 必填
+    pub async fn request_partner_h5_order(
+        &self,
+        body: &PartnerOrderRequestBody<'_>,
+    ) -> Result<H5OrderResponseData, WxpayApiError> {
+        self.post("/v3/pay/partner/transactions/h5", body).await
+    }
+
+    /// [服务商模式Native下单](https://pay.weixin.qq.com/doc/v3/partner/4012085784)
+    pub async fn request_partner_native_order(
+        &self,
+        body: &PartnerOrderRequestBody<'_>,
+    ) -> Result<NativeOrderResponseData, WxpayApiError> {
+        self.post("/v3/pay/partner/transactions/native", body).await
+    }
+
+    /// [服务商模式商户订单号查询订单](https://pay.weixin.qq.com/doc/v3/partner/4012085791)
+    pub async fn request_partner_order_detail_by_out_trade_no(
+        &self,
+        sub_mchid: &str,
+        out_trade_no: &str,
+    ) -> Result<PartnerOrderDetailResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/pay/partner/transactions/out-trade-no/{}?sp_mchid={}&sub_mchid={}",
+            out_trade_no,
+            self.mchid(),
+            sub_mchid
+        ))
+        .await
+    }
+
+    /// [服务商模式微信支付订单号查询订单](https://pay.weixin.qq.com/doc/v3/partner/4012085790)
+    pub async fn request_partner_order_detail_by_transaction_id(
+        &self,
+        sub_mchid: &str,
+        transaction_id: &str,
+    ) -> Result<PartnerOrderDetailResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/pay/partner/transactions/id/{}?sp_mchid={}&sub_mchid={}",
+            transaction_id,
+            self.mchid(),
+            sub_mchid
+        ))
+        .await
+    }
+
+    /// [服务商模式关闭订单](https://pay.weixin.qq.com/doc/v3/partner/4012085794)
+    pub async fn request_partner_close_order(
+        &self,
+        sub_mchid: &str,
+        out_trade_no: &str,
+    ) -> Result<(), WxpayApiError> {
+        self.post_without_response(
+            &format!(
+                "/v3/pay/partner/transactions/out-trade-no/{}/close",
+                out_trade_no
+            ),
+            &PartnerCloseOrderRequestBody {
+                sp_mchid: self.mchid(),
+                sub_mchid,
+            },
+        )
+        .await
+    }
+
+    /// [服务商模式退款申请](https://pay.weixin.qq.com/doc/v3/partner/4012085949)
+    pub async fn request_partner_refund_order(
+        &self,
+        body: &PartnerRefundOrderRequestBody<'_>,
+    ) -> Result<RefundOrderResponseData, WxpayApiError> {
+        self.post("/v3/refund/domestic/refunds", body).await
+    }
+
+    /// [服务商模式查询单笔退款](https://pay.weixin.qq.com/doc/v3/partner/4012085958)
+    pub async fn request_partner_refund_detail(
+        &self,
+        sub_mchid: &str,
+        out_refund_no: &str,
+    ) -> Result<RefundOrderResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/refund/domestic/refunds/{}?sub_mchid={}",
+            out_refund_no, sub_mchid
+        ))
+        .await
+    }
+}
+
+#[test]
+fn test_serialize_partner_refund_order() {
+    use super::api::{OrderId, RefundAmount};
+
+    let body = PartnerRefundOrderRequestBody {
+        sub_mchid: "1900000109",
+        refund: RefundOrderRequestBody {
+            order_id: OrderId::TransactionId("1217752501201407033233368018".into()),
+            out_refund_no: "1217752501201407033233368018",
+            reason: None,
+            notify_url: None,
+            funds_account: None,
+            amount: RefundAmount {
+                refund: 888,
+                from: None,
+                total: 888,
+                currency: "CNY",
+            },
+            goods_detail: None,
+        },
+    };
+    assert_eq!(
+        serde_json::to_value(&body).unwrap(),
+        serde_json::json!({
+            "sub_mchid": "1900000109",
+            "transaction_id": "1217752501201407033233368018",
+            "out_refund_no": "1217752501201407033233368018",
+            "amount": {"refund": 888, "total": 888, "currency": "CNY"},
+        })
+    );
+
+    let data: PartnerOrderDetailResponseData = serde_json::from_str(
+        r#"{"sp_appid":"wx8888888888888888","sp_mchid":"1230000109","sub_appid":"wxd678efh567hg6999","sub_mchid":"1900000109","out_trade_no":"1217752501201407033233368018","transaction_id":"1217752501201407033233368018","trade_type":"JSAPI","trade_state":"SUCCESS","trade_state_desc":"支付成功","bank_type":"CMC","success_time":"2018-06-08T10:34:56+08:00","payer":{"sp_openid":"oUpF8uMuAJO_M2pxb1Q9zNjWeS6o"},"amount":{"total":100,"payer_total":100,"currency":"CNY","payer_currency":"CNY"}}"#,
+    )
+    .unwrap();
+    assert_eq!(data.trade_state, TradeState::Success);
+    assert_eq!(
+        data.payer.unwrap().sp_openid.as_deref(),
+        Some("oUpF8uMuAJO_M2pxb1Q9zNjWeS6o")
+    );
+}
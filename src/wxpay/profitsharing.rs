@@ -0,0 +1,429 @@
+//! [分账](https://pay.weixin.qq.com/doc/v3/merchant/4012068478)
+//!
+//! 下单时设置 [`SettleInfo`](super::api::SettleInfo) 的 `profit_sharing` 为 `true`，支付成功后订单资金会被冻结，
+//! 再通过请求分账将资金分给已添加的分账接收方，完成分账后需要解冻剩余资金：
+//!
+//! ```ignore
+//! client.request_add_profit_sharing_receiver(&receiver).await?;
+//! let order = client.request_profit_sharing_order(&body).await?;
+//! // 分账结果以查询或回调通知为准
+//! let order = client.request_profit_sharing_order_detail(&transaction_id, &out_order_no).await?;
+//! ```
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    client::WxpayClient,
+    error::WxpayApiError,
+    sensitive::{SensitiveField, SensitiveFields},
+};
+
+/// 分账接收方类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReceiverType {
+    /// 商户号
+    MerchantId,
+    /// 个人 openid（由父商户 appid 转换得到）
+    PersonalOpenid,
+    /// 个人 sub_openid（由子商户 appid 转换得到）
+    PersonalSubOpenid,
+    #[serde(other)]
+    Unknown,
+}
+
+/// 分账接收方与商户的关系
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReceiverRelationType {
+    /// 门店
+    Store,
+    /// 员工
+    Staff,
+    /// 店主
+    StoreOwner,
+    /// 合作伙伴
+    Partner,
+    /// 总部
+    Headquarter,
+    /// 品牌方
+    Brand,
+    /// 分销商
+    Distributor,
+    /// 用户
+    User,
+    /// 供应商
+    Supplier,
+    /// 自定义，需要填写 `custom_relation`
+    Custom,
+    #[serde(other)]
+    Unknown,
+}
+
+/// 分账单状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProfitSharingOrderState {
+    /// 处理中
+    Processing,
+    /// 分账完成
+    Finished,
+    #[serde(other)]
+    Unknown,
+}
+
+/// 分账接收方的分账结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProfitSharingResult {
+    /// 待分账
+    Pending,
+    /// 分账成功
+    Success,
+    /// 已关闭
+    Closed,
+    #[serde(other)]
+    Unknown,
+}
+
+/// 分账失败原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProfitSharingFailReason {
+    /// 分账接收账户异常
+    AccountAbnormal,
+    /// 分账关系已解除
+    NoRelation,
+    /// 高风险接收方
+    ReceiverHighRisk,
+    /// 接收方未实名
+    ReceiverRealNameNotVerified,
+    /// 分账权限已解除
+    NoAuth,
+    /// 接收方已达收款限额
+    ReceiverReceiptLimit,
+    /// 分出方账户异常
+    PayerAccountAbnormal,
+    /// 描述参数设置失败
+    InvalidRequest,
+    #[serde(other)]
+    Unknown,
+}
+
+/// 分账回退结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProfitSharingReturnResult {
+    /// 处理中
+    Processing,
+    /// 已成功
+    Success,
+    /// 已失败
+    Failed,
+    #[serde(other)]
+    Unknown,
+}
+
+/// 分账回退失败原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProfitSharingReturnFailReason {
+    /// 原分账接收方账户异常
+    AccountAbnormal,
+    /// 超时关单
+    TimeOutClosed,
+    /// 原分账分出方账户异常
+    PayerAccountAbnormal,
+    /// 描述参数设置失败
+    InvalidRequest,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AddReceiverRequestBody {
+    pub appid: String,
+    #[serde(rename = "type")]
+    pub receiver_type: ReceiverType,
+    /// 类型为商户号时填商户号，为个人时填 openid
+    pub account: String,
+    /// 分账接收方全称，类型为商户号时必填，需要加密
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<SensitiveField>,
+    pub relation_type: ReceiverRelationType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_relation: Option<String>,
+}
+
+impl SensitiveFields for AddReceiverRequestBody {
+    fn sensitive_fields(&mut self) -> Vec<&mut SensitiveField> {
+        self.name.iter_mut().collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AddReceiverResponseData {
+    #[serde(rename = "type")]
+    pub receiver_type: ReceiverType,
+    pub account: String,
+    pub name: Option<SensitiveField>,
+    pub relation_type: ReceiverRelationType,
+    pub custom_relation: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DeleteReceiverRequestBody {
+    pub appid: String,
+    #[serde(rename = "type")]
+    pub receiver_type: ReceiverType,
+    pub account: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeleteReceiverResponseData {
+    #[serde(rename = "type")]
+    pub receiver_type: ReceiverType,
+    pub account: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProfitSharingOrderRequestBody {
+    pub appid: String,
+    pub transaction_id: String,
+    /// 商户分账单号
+    pub out_order_no: String,
+    /// 最多 50 个分账接收方
+    pub receivers: Vec<ProfitSharingReceiver>,
+    /// 是否解冻剩余未分资金，为 `true` 时分账完成后自动解冻，不需要再调用解冻剩余资金
+    pub unfreeze_unsplit: bool,
+}
+
+impl SensitiveFields for ProfitSharingOrderRequestBody {
+    fn sensitive_fields(&mut self) -> Vec<&mut SensitiveField> {
+        self.receivers
+            .iter_mut()
+            .filter_map(|receiver| receiver.name.as_mut())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProfitSharingReceiver {
+    #[serde(rename = "type")]
+    pub receiver_type: ReceiverType,
+    pub account: String,
+    /// 分账个人接收方姓名，选填，需要加密
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<SensitiveField>,
+    /// 分账金额，单位为分
+    pub amount: i64,
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProfitSharingOrderResponseData {
+    pub transaction_id: String,
+    pub out_order_no: String,
+    /// 微信分账单号
+    pub order_id: String,
+    pub state: ProfitSharingOrderState,
+    #[serde(default)]
+    pub receivers: Vec<ProfitSharingReceiverResult>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProfitSharingReceiverResult {
+    pub amount: i64,
+    pub description: String,
+    #[serde(rename = "type")]
+    pub receiver_type: ReceiverType,
+    pub account: String,
+    pub result: ProfitSharingResult,
+    pub fail_reason: Option<ProfitSharingFailReason>,
+    /// 分账明细单号
+    pub detail_id: String,
+    pub create_time: DateTime<FixedOffset>,
+    pub finish_time: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProfitSharingReturnRequestBody {
+    /// 微信分账单号，和 `out_order_no` 二选一
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub out_order_no: Option<String>,
+    /// 商户回退单号
+    pub out_return_no: String,
+    /// 回退商户号，只能是分账接收方商户号
+    pub return_mchid: String,
+    /// 回退金额，单位为分
+    pub amount: i64,
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProfitSharingReturnResponseData {
+    pub order_id: String,
+    pub out_order_no: String,
+    pub out_return_no: String,
+    /// 微信回退单号
+    pub return_id: String,
+    pub return_mchid: String,
+    pub amount: i64,
+    pub description: String,
+    pub result: ProfitSharingReturnResult,
+    pub fail_reason: Option<ProfitSharingReturnFailReason>,
+    pub create_time: DateTime<FixedOffset>,
+    pub finish_time: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UnfreezeRequestBody {
+    pub transaction_id: String,
+    pub out_order_no: String,
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UnsplitAmountResponseData {
+    pub transaction_id: String,
+    /// 订单剩余待分金额，单位为分
+    pub unsplit_amount: i64,
+}
+
+/// [分账动账回调通知](https://pay.weixin.qq.com/doc/v3/merchant/4012068488)解密后的数据
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProfitSharingCallbackResourceData {
+    /// 直连商户号，服务商模式下为空
+    pub mchid: Option<String>,
+    /// 服务商商户号，直连模式下为空
+    pub sp_mchid: Option<String>,
+    pub sub_mchid: Option<String>,
+    pub transaction_id: String,
+    pub order_id: String,
+    pub out_order_no: String,
+    pub receiver: ProfitSharingCallbackReceiver,
+    pub success_time: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProfitSharingCallbackReceiver {
+    #[serde(rename = "type")]
+    pub receiver_type: ReceiverType,
+    pub account: String,
+    pub amount: i64,
+    pub description: String,
+}
+
+impl WxpayClient {
+    /// [添加分账接收方](https://pay.weixin.qq.com/doc/v3/merchant/4012068500)，使用当前的平台证书自动加密 `name`
+    pub async fn request_add_profit_sharing_receiver(
+        &self,
+        body: &AddReceiverRequestBody,
+    ) -> Result<AddReceiverResponseData, WxpayApiError> {
+        let text = self
+            .post_sensitive("/v3/profitsharing/receivers/add", body)
+            .await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// [删除分账接收方](https://pay.weixin.qq.com/doc/v3/merchant/4012068502)
+    pub async fn request_delete_profit_sharing_receiver(
+        &self,
+        body: &DeleteReceiverRequestBody,
+    ) -> Result<DeleteReceiverResponseData, WxpayApiError> {
+        self.post("/v3/profitsharing/receivers/delete", body).await
+    }
+
+    /// [请求分账](https://pay.weixin.qq.com/doc/v3/merchant/4012068494)，使用当前的平台证书自动加密接收方姓名
+    pub async fn request_profit_sharing_order(
+        &self,
+        body: &ProfitSharingOrderRequestBody,
+    ) -> Result<ProfitSharingOrderResponseData, WxpayApiError> {
+        let text = self
+            .post_sensitive("/v3/profitsharing/orders", body)
+            .await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// [查询分账结果](https://pay.weixin.qq.com/doc/v3/merchant/4012068495)
+    pub async fn request_profit_sharing_order_detail(
+        &self,
+        transaction_id: &str,
+        out_order_no: &str,
+    ) -> Result<ProfitSharingOrderResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/profitsharing/orders/{}?transaction_id={}",
+            out_order_no, transaction_id
+        ))
+        .await
+    }
+
+    /// [请求分账回退](https://pay.weixin.qq.com/doc/v3/merchant/4012068496)
+    pub async fn request_profit_sharing_return(
+        &self,
+        body: &ProfitSharingReturnRequestBody,
+    ) -> Result<ProfitSharingReturnResponseData, WxpayApiError> {
+        self.post("/v3/profitsharing/return-orders", body).await
+    }
+
+    /// [查询分账回退结果](https://pay.weixin.qq.com/doc/v3/merchant/4012068497)
+    pub async fn request_profit_sharing_return_detail(
+        &self,
+        out_order_no: &str,
+        out_return_no: &str,
+    ) -> Result<ProfitSharingReturnResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/profitsharing/return-orders/{}?out_order_no={}",
+            out_return_no, out_order_no
+        ))
+        .await
+    }
+
+    /// [解冻剩余资金](https://pay.weixin.qq.com/doc/v3/merchant/4012068498)
+    pub async fn request_profit_sharing_unfreeze(
+        &self,
+        body: &UnfreezeRequestBody,
+    ) -> Result<ProfitSharingOrderResponseData, WxpayApiError> {
+        self.post("/v3/profitsharing/orders/unfreeze", body).await
+    }
+
+    /// [查询剩余待分金额](https://pay.weixin.qq.com/doc/v3/merchant/4012068499)
+    pub async fn request_profit_sharing_unsplit_amount(
+        &self,
+        transaction_id: &str,
+    ) -> Result<UnsplitAmountResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/profitsharing/transactions/{}/amounts",
+            transaction_id
+        ))
+        .await
+    }
+}
+
+#[test]
+fn test_deserialize_profit_sharing_order() {
+    let data: ProfitSharingOrderResponseData = serde_json::from_str(
+        r#"{"transaction_id":"4208450740201411110007820472","out_order_no":"P20150806125346","order_id":"3008450740201411110007820472","state":"FINISHED","receivers":[{"amount":100,"description":"分给商户1900000109","type":"MERCHANT_ID","account":"1900000109","result":"CLOSED","fail_reason":"ACCOUNT_ABNORMAL","detail_id":"36011111111111111111111","create_time":"2015-05-20T13:29:35.120+08:00","finish_time":"2015-05-20T13:29:35.120+08:00"}]}"#,
+    )
+    .unwrap();
+    assert_eq!(data.state, ProfitSharingOrderState::Finished);
+    let receiver = &data.receivers[0];
+    assert_eq!(receiver.receiver_type, ReceiverType::MerchantId);
+    assert_eq!(receiver.result, ProfitSharingResult::Closed);
+    assert_eq!(
+        receiver.fail_reason,
+        Some(ProfitSharingFailReason::AccountAbnormal)
+    );
+
+    let body = DeleteReceiverRequestBody {
+        appid: "wx8888888888888888".into(),
+        receiver_type: ReceiverType::PersonalOpenid,
+        account: "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o".into(),
+    };
+    assert_eq!(
+        serde_json::to_value(&body).unwrap()["type"],
+        "PERSONAL_OPENID"
+    );
+}
@@ -0,0 +1,45 @@
+//! 将 [Native 支付](super::api::request_native_order)返回的 `code_url` 渲染为二维码图片
+//!
+//! ```ignore
+//! let res_data = client.request_native_order(&body).await?;
+//! let svg = render_code_url_svg(&res_data.code_url, 256)?;
+//! ```
+use std::io::Cursor;
+
+use image::{ImageFormat, Luma};
+use qrcode::{render::svg, QrCode};
+
+use super::error::WxpayApiError;
+
+/// 渲染为 svg 格式的二维码，`size` 为图片的最小宽高（像素）
+pub fn render_code_url_svg(code_url: &str, size: u32) -> Result<String, WxpayApiError> {
+    let code =
+        QrCode::new(code_url.as_bytes()).map_err(|e| WxpayApiError::QrCodeError(e.to_string()))?;
+    Ok(code
+        .render::<svg::Color>()
+        .min_dimensions(size, size)
+        .build())
+}
+
+/// 渲染为 png 格式的二维码，`size` 为图片的最小宽高（像素）
+pub fn render_code_url_png(code_url: &str, size: u32) -> Result<Vec<u8>, WxpayApiError> {
+    let code =
+        QrCode::new(code_url.as_bytes()).map_err(|e| WxpayApiError::QrCodeError(e.to_string()))?;
+    let image = code.render::<Luma<u8>>().min_dimensions(size, size).build();
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| WxpayApiError::QrCodeError(e.to_string()))?;
+    Ok(png)
+}
+
+#[test]
+fn test_render_code_url() {
+    let code_url = "weixin://wxpay/bizpayurl?pr=p4lpSuKzz";
+
+    let svg = render_code_url_svg(code_url, 256).unwrap();
+    assert!(svg.contains("<svg"));
+
+    let png = render_code_url_png(code_url, 256).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+}
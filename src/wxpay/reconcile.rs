@@ -0,0 +1,411 @@
+//! 使用[交易账单](super::bill::TradeBill)与本地订单对账
+//!
+//! 本地订单通过 [`LocalOrderStore`] 获取，可以对接任意存储：
+//!
+//! ```ignore
+//! use wechat_vendor_sdk::async_trait;
+//! use wechat_vendor_sdk::wxpay::reconcile::{LocalOrder, LocalOrderStore, LocalOrderStoreError};
+//!
+//! struct DbOrderStore(PgPool);
+//!
+//! #[async_trait]
+//! impl LocalOrderStore for DbOrderStore {
+//!     async fn paid_orders(&self, bill_date: &str) -> Result<Vec<LocalOrder>, LocalOrderStoreError> {
+//!         // 查询账单日期内支付成功的订单
+//!     }
+//!     async fn order(&self, out_trade_no: &str) -> Result<Option<LocalOrder>, LocalOrderStoreError> {
+//!         // 按商户订单号查询订单
+//!     }
+//! }
+//!
+//! let bill = client.download_trade_bill(&query).await?;
+//! let report = reconcile_trade_bill(&DbOrderStore(pool), "2024-06-01", &bill).await?;
+//! for mismatch in &report.mismatches {
+//!     println!("{:?}", mismatch);
+//! }
+//! ```
+use std::collections::HashMap;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use async_trait::async_trait;
+
+use super::{
+    api::TradeState,
+    bill::{TradeBill, TradeBillRow},
+};
+
+/// 本地订单存储返回的错误
+#[derive(Error, Debug)]
+#[error(transparent)]
+pub struct LocalOrderStoreError(#[from] Box<dyn std::error::Error + Send + Sync>);
+
+impl LocalOrderStoreError {
+    pub fn new(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self(err.into())
+    }
+}
+
+/// 本地订单状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LocalOrderStatus {
+    /// 未支付
+    Unpaid,
+    /// 已支付
+    Paid,
+    /// 已支付且全额退款
+    Refunded,
+    /// 已支付且部分退款
+    PartiallyRefunded,
+    /// 已关闭
+    Closed,
+}
+
+impl LocalOrderStatus {
+    fn is_paid(&self) -> bool {
+        matches!(
+            self,
+            LocalOrderStatus::Paid
+                | LocalOrderStatus::Refunded
+                | LocalOrderStatus::PartiallyRefunded
+        )
+    }
+
+    fn is_refunded(&self) -> bool {
+        matches!(
+            self,
+            LocalOrderStatus::Refunded | LocalOrderStatus::PartiallyRefunded
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LocalOrder {
+    pub out_trade_no: String,
+    /// 订单金额，单位为分
+    pub amount: i64,
+    pub status: LocalOrderStatus,
+}
+
+/// 对账所需的本地订单
+#[async_trait]
+pub trait LocalOrderStore: Send + Sync {
+    /// 账单日期（`yyyy-MM-DD`）内本地记录为支付成功的订单
+    async fn paid_orders(&self, bill_date: &str) -> Result<Vec<LocalOrder>, LocalOrderStoreError>;
+
+    /// 按商户订单号查询订单，用于账单中出现、但不在当日已支付订单中的记录（如跨日支付、历史订单的退款）
+    async fn order(&self, out_trade_no: &str) -> Result<Option<LocalOrder>, LocalOrderStoreError>;
+}
+
+/// 对账发现的差异，金额单位为分
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ReconcileMismatch {
+    /// 本地已支付，账单中没有支付记录
+    MissingInBill { out_trade_no: String, amount: i64 },
+    /// 账单中支付成功，本地没有该订单
+    MissingLocally {
+        out_trade_no: String,
+        transaction_id: String,
+        amount: i64,
+    },
+    /// 本地订单金额和账单订单金额不一致
+    AmountMismatch {
+        out_trade_no: String,
+        local_amount: i64,
+        bill_amount: i64,
+    },
+    /// 账单中支付成功，本地订单不是已支付状态
+    StatusMismatch {
+        out_trade_no: String,
+        local_status: LocalOrderStatus,
+        bill_state: TradeState,
+    },
+    /// 账单中的退款找不到对应的本地订单
+    RefundWithoutOrder {
+        out_trade_no: String,
+        out_refund_no: String,
+        refund_amount: i64,
+    },
+    /// 账单中有退款，本地订单不是已退款或部分退款状态，`refund_amount` 为账单中该订单的退款总额
+    RefundStatusMismatch {
+        out_trade_no: String,
+        local_status: LocalOrderStatus,
+        refund_amount: i64,
+    },
+    /// 账单中该订单的退款总额超过本地订单金额
+    RefundAmountMismatch {
+        out_trade_no: String,
+        local_amount: i64,
+        refund_amount: i64,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReconcileReport {
+    /// 一致的支付记录数
+    pub matched: usize,
+    pub mismatches: Vec<ReconcileMismatch>,
+}
+
+impl ReconcileReport {
+    /// 是否没有任何差异
+    pub fn is_balanced(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// 使用交易账单（[`TradeBillType::All`](super::bill::TradeBillType::All)）与本地订单对账
+pub async fn reconcile_trade_bill(
+    store: &dyn LocalOrderStore,
+    bill_date: &str,
+    bill: &TradeBill,
+) -> Result<ReconcileReport, LocalOrderStoreError> {
+    let mut report = ReconcileReport::default();
+    let mut paid_orders: HashMap<String, LocalOrder> = store
+        .paid_orders(bill_date)
+        .await?
+        .into_iter()
+        .map(|order| (order.out_trade_no.clone(), order))
+        .collect();
+    // 账单中出现过支付记录的订单，本地没有该订单时为 `None`（已记录为 MissingLocally）
+    let mut billed: HashMap<&str, Option<LocalOrder>> = HashMap::new();
+
+    for row in bill
+        .rows
+        .iter()
+        .filter(|row| row.trade_state == TradeState::Success)
+    {
+        let order = match paid_orders.remove(&row.out_trade_no) {
+            Some(order) => order,
+            None => match store.order(&row.out_trade_no).await? {
+                Some(order) => order,
+                None => {
+                    billed.insert(row.out_trade_no.as_str(), None);
+                    report.mismatches.push(ReconcileMismatch::MissingLocally {
+                        out_trade_no: row.out_trade_no.clone(),
+                        transaction_id: row.transaction_id.clone(),
+                        amount: row.total_fee,
+                    });
+                    continue;
+                }
+            },
+        };
+        billed.insert(row.out_trade_no.as_str(), Some(order.clone()));
+
+        if !order.status.is_paid() {
+            report.mismatches.push(ReconcileMismatch::StatusMismatch {
+                out_trade_no: order.out_trade_no,
+                local_status: order.status,
+                bill_state: row.trade_state,
+            });
+        } else if order.amount != row.total_fee {
+            report.mismatches.push(ReconcileMismatch::AmountMismatch {
+                out_trade_no: order.out_trade_no,
+                local_amount: order.amount,
+                bill_amount: row.total_fee,
+            });
+        } else {
+            report.matched += 1;
+        }
+    }
+
+    // 同一订单可能有多笔（部分）退款，按订单汇总后和本地订单比较，保持在账单中首次出现的顺序
+    let mut refunds: Vec<(&str, Vec<&TradeBillRow>)> = Vec::new();
+    for row in bill
+        .rows
+        .iter()
+        .filter(|row| row.trade_state == TradeState::Refund)
+    {
+        match refunds
+            .iter_mut()
+            .find(|(out_trade_no, _)| *out_trade_no == row.out_trade_no)
+        {
+            Some((_, rows)) => rows.push(row),
+            None => refunds.push((row.out_trade_no.as_str(), vec![row])),
+        }
+    }
+
+    for (out_trade_no, rows) in refunds {
+        let order = match billed.get(out_trade_no) {
+            Some(Some(order)) => order.clone(),
+            Some(None) => continue,
+            None => match store.order(out_trade_no).await? {
+                Some(order) => order,
+                None => {
+                    report.mismatches.extend(rows.iter().map(|row| {
+                        ReconcileMismatch::RefundWithoutOrder {
+                            out_trade_no: row.out_trade_no.clone(),
+                            out_refund_no: row.out_refund_no.clone(),
+                            refund_amount: row.refund_fee,
+                        }
+                    }));
+                    continue;
+                }
+            },
+        };
+
+        let refund_amount = rows.iter().map(|row| row.refund_fee).sum();
+        if !order.status.is_refunded() {
+            report
+                .mismatches
+                .push(ReconcileMismatch::RefundStatusMismatch {
+                    out_trade_no: order.out_trade_no,
+                    local_status: order.status,
+                    refund_amount,
+                });
+        } else if refund_amount > order.amount {
+            report
+                .mismatches
+                .push(ReconcileMismatch::RefundAmountMismatch {
+                    out_trade_no: order.out_trade_no,
+                    local_amount: order.amount,
+                    refund_amount,
+                });
+        }
+    }
+
+    let mut missing_in_bill: Vec<_> = paid_orders.into_values().collect();
+    missing_in_bill.sort_by(|a, b| a.out_trade_no.cmp(&b.out_trade_no));
+    report
+        .mismatches
+        .extend(
+            missing_in_bill
+                .into_iter()
+                .map(|order| ReconcileMismatch::MissingInBill {
+                    out_trade_no: order.out_trade_no,
+                    amount: order.amount,
+                }),
+        );
+
+    Ok(report)
+}
+
+/// 从 csv 读取的本地订单：out_trade_no,amount,status,paid_date
+#[cfg(test)]
+struct FixtureOrderStore(Vec<(LocalOrder, String)>);
+
+#[cfg(test)]
+impl FixtureOrderStore {
+    fn parse(content: &str) -> Self {
+        let orders = content
+            .lines()
+            .skip(1)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let columns: Vec<&str> = line.split(',').collect();
+                let status = match columns[2] {
+                    "UNPAID" => LocalOrderStatus::Unpaid,
+                    "PAID" => LocalOrderStatus::Paid,
+                    "REFUNDED" => LocalOrderStatus::Refunded,
+                    "PARTIALLY_REFUNDED" => LocalOrderStatus::PartiallyRefunded,
+                    "CLOSED" => LocalOrderStatus::Closed,
+                    status => panic!("unknown status {}", status),
+                };
+                let order = LocalOrder {
+                    out_trade_no: columns[0].into(),
+                    amount: columns[1].parse().unwrap(),
+                    status,
+                };
+                (order, columns[3].to_string())
+            })
+            .collect();
+        Self(orders)
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl LocalOrderStore for FixtureOrderStore {
+    async fn paid_orders(&self, bill_date: &str) -> Result<Vec<LocalOrder>, LocalOrderStoreError> {
+        Ok(self
+            .0
+            .iter()
+            .filter(|(order, paid_date)| order.status.is_paid() && paid_date == bill_date)
+            .map(|(order, _)| order.clone())
+            .collect())
+    }
+
+    async fn order(&self, out_trade_no: &str) -> Result<Option<LocalOrder>, LocalOrderStoreError> {
+        Ok(self
+            .0
+            .iter()
+            .find(|(order, _)| order.out_trade_no == out_trade_no)
+            .map(|(order, _)| order.clone()))
+    }
+}
+
+#[test]
+fn test_reconcile_trade_bill() {
+    let store = FixtureOrderStore::parse(include_str!("../../fixtures/wxpay/local_orders.csv"));
+    let bill = TradeBill::parse(include_str!(
+        "../../fixtures/wxpay/trade_bill_reconcile.csv"
+    ))
+    .unwrap();
+
+    let report =
+        crate::common::block_on(reconcile_trade_bill(&store, "2024-06-01", &bill)).unwrap();
+    assert_eq!(report.matched, 2);
+    assert_eq!(
+        report.mismatches,
+        vec![
+            ReconcileMismatch::AmountMismatch {
+                out_trade_no: "ORDER0002".into(),
+                local_amount: 2500,
+                bill_amount: 2000,
+            },
+            ReconcileMismatch::StatusMismatch {
+                out_trade_no: "ORDER0003".into(),
+                local_status: LocalOrderStatus::Unpaid,
+                bill_state: TradeState::Success,
+            },
+            ReconcileMismatch::MissingLocally {
+                out_trade_no: "ORDER0005".into(),
+                transaction_id: "4200000001202406010000000005".into(),
+                amount: 600,
+            },
+            ReconcileMismatch::RefundWithoutOrder {
+                out_trade_no: "ORDER0006".into(),
+                out_refund_no: "REFUND0006".into(),
+                refund_amount: 200,
+            },
+            ReconcileMismatch::RefundStatusMismatch {
+                out_trade_no: "ORDER0008".into(),
+                local_status: LocalOrderStatus::Paid,
+                refund_amount: 100,
+            },
+            ReconcileMismatch::RefundAmountMismatch {
+                out_trade_no: "ORDER0009".into(),
+                local_amount: 100,
+                refund_amount: 200,
+            },
+            // 两笔部分退款合计超过订单金额
+            ReconcileMismatch::RefundAmountMismatch {
+                out_trade_no: "ORDER0010".into(),
+                local_amount: 10000,
+                refund_amount: 12000,
+            },
+            ReconcileMismatch::MissingInBill {
+                out_trade_no: "ORDER0004".into(),
+                amount: 800,
+            },
+        ]
+    );
+    assert!(!report.is_balanced());
+}
+
+#[test]
+fn test_reconcile_balanced_bill() {
+    let store = FixtureOrderStore::parse(
+        "out_trade_no,amount,status,paid_date\n\
+         ORDER0001,1000,REFUNDED,2024-06-01\n\
+         ORDER0002,2000,PAID,2024-06-01\n\
+         ORDER0003,1300,PAID,2024-06-01\n",
+    );
+    let bill = TradeBill::parse(super::bill::TRADE_BILL_FIXTURE).unwrap();
+
+    let report =
+        crate::common::block_on(reconcile_trade_bill(&store, "2024-06-01", &bill)).unwrap();
+    assert_eq!(report.matched, 3);
+    assert!(report.is_balanced());
+}
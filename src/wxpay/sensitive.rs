@@ -0,0 +1,218 @@
+//! [敏感信息加解密](https://pay.weixin.qq.com/doc/v3/merchant/4012070130)
+//!
+//! 请求中的敏感信息（如转账的收款用户姓名）需要使用平台证书公钥或微信支付公钥进行 RSA-OAEP 加密，
+//! 并在请求头 `Wechatpay-Serial` 中带上对应的序列号。请求体中使用 [`SensitiveField`] 标记敏感字段，
+//! [`WxpayClient`] 发送请求前会使用当前的平台证书自动加密：
+//!
+//! ```ignore
+//! let body = BatchTransferRequestBody {
+//!     transfer_detail_list: vec![TransferDetail {
+//!         user_name: Some("张三".into()),
+//!         ..
+//!     }],
+//!     ..
+//! };
+//! client.request_batch_transfer(&body).await?;
+//! ```
+use std::fmt;
+
+use base64::{engine::general_purpose, Engine as _};
+use rsa::{Oaep, RsaPrivateKey, RsaPublicKey};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha1::Sha1;
+
+use super::{
+    client::WxpayClient,
+    error::WxpayApiError,
+    utils::{parse_private_key, parse_public_key},
+};
+
+/// 请求或响应中的敏感字段
+///
+/// 使用明文构造（`"张三".into()`）的字段需要加密后才能序列化，直接序列化会返回错误，避免明文被发送出去；
+/// 反序列化得到的是密文，使用 [`WxpayClient::decrypt_sensitive`] 解密。
+#[derive(Clone, PartialEq, Eq)]
+pub enum SensitiveField {
+    Plain(String),
+    Encrypted(String),
+}
+
+impl SensitiveField {
+    /// 使用已加密的密文构造
+    pub fn encrypted(ciphertext: impl Into<String>) -> Self {
+        SensitiveField::Encrypted(ciphertext.into())
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self, SensitiveField::Encrypted(_))
+    }
+
+    /// 明文字段使用公钥加密，已加密的字段保持不变
+    pub(crate) fn encrypt(&mut self, public_key: &RsaPublicKey) -> Result<(), WxpayApiError> {
+        if let SensitiveField::Plain(plaintext) = self {
+            *self = SensitiveField::Encrypted(encrypt_sensitive(public_key, plaintext)?);
+        }
+        Ok(())
+    }
+}
+
+impl From<String> for SensitiveField {
+    fn from(plaintext: String) -> Self {
+        SensitiveField::Plain(plaintext)
+    }
+}
+
+impl From<&str> for SensitiveField {
+    fn from(plaintext: &str) -> Self {
+        SensitiveField::Plain(plaintext.into())
+    }
+}
+
+impl fmt::Debug for SensitiveField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // 不在日志中输出明文
+            SensitiveField::Plain(_) => f.write_str("Plain(***)"),
+            SensitiveField::Encrypted(ciphertext) => {
+                f.debug_tuple("Encrypted").field(ciphertext).finish()
+            }
+        }
+    }
+}
+
+impl Serialize for SensitiveField {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            SensitiveField::Plain(_) => Err(serde::ser::Error::custom(
+                "sensitive field must be encrypted before serializing",
+            )),
+            SensitiveField::Encrypted(ciphertext) => serializer.serialize_str(ciphertext),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SensitiveField {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(SensitiveField::Encrypted(String::deserialize(
+            deserializer,
+        )?))
+    }
+}
+
+/// 包含敏感字段的请求体
+pub trait SensitiveFields {
+    /// 请求体中所有的敏感字段
+    fn sensitive_fields(&mut self) -> Vec<&mut SensitiveField>;
+
+    /// 使用公钥加密所有明文的敏感字段
+    fn encrypt_sensitive_fields(&mut self, public_key: &RsaPublicKey) -> Result<(), WxpayApiError> {
+        self.sensitive_fields()
+            .into_iter()
+            .try_for_each(|field| field.encrypt(public_key))
+    }
+}
+
+/// 使用平台证书公钥或微信支付公钥（pem 格式）加密敏感信息
+pub fn encrypt_wxpay_sensitive(
+    wx_public_key: &str,
+    plaintext: &str,
+) -> Result<String, WxpayApiError> {
+    encrypt_sensitive(&parse_public_key(wx_public_key)?, plaintext)
+}
+
+/// 使用商户私钥（pkcs8 pem 格式）解密响应中的敏感信息
+pub fn decrypt_wxpay_sensitive(
+    mch_private_key: &str,
+    ciphertext: &str,
+) -> Result<String, WxpayApiError> {
+    decrypt_sensitive(&parse_private_key(mch_private_key)?, ciphertext)
+}
+
+pub(crate) fn encrypt_sensitive(
+    public_key: &RsaPublicKey,
+    plaintext: &str,
+) -> Result<String, WxpayApiError> {
+    let ciphertext = public_key
+        .encrypt(
+            &mut rand::thread_rng(),
+            Oaep::new::<Sha1>(),
+            plaintext.as_bytes(),
+        )
+        .map_err(|_| WxpayApiError::EncryptFailed)?;
+    Ok(general_purpose::STANDARD.encode(ciphertext))
+}
+
+pub(crate) fn decrypt_sensitive(
+    private_key: &RsaPrivateKey,
+    ciphertext: &str,
+) -> Result<String, WxpayApiError> {
+    let ciphertext = general_purpose::STANDARD.decode(ciphertext)?;
+    let plaintext = private_key
+        .decrypt(Oaep::new::<Sha1>(), &ciphertext)
+        .map_err(|_| WxpayApiError::DecryptFailed)?;
+    String::from_utf8(plaintext).map_err(|_| WxpayApiError::DecryptFailed)
+}
+
+impl WxpayClient {
+    /// 使用当前的平台证书或微信支付公钥加密敏感信息，返回 (序列号, 密文)，序列号需要放在请求头 `Wechatpay-Serial` 中
+    pub async fn encrypt_sensitive(
+        &self,
+        plaintext: &str,
+    ) -> Result<(String, String), WxpayApiError> {
+        let (serial_no, public_key) = self.newest_platform_public_key().await?;
+        Ok((serial_no, encrypt_sensitive(&public_key, plaintext)?))
+    }
+
+    /// 使用商户私钥解密响应中的敏感信息
+    pub fn decrypt_sensitive(&self, field: &SensitiveField) -> Result<String, WxpayApiError> {
+        match field {
+            SensitiveField::Plain(plaintext) => Ok(plaintext.clone()),
+            SensitiveField::Encrypted(ciphertext) => {
+                decrypt_sensitive(self.mch_private_key(), ciphertext)
+            }
+        }
+    }
+
+    /// 加密请求体中的敏感字段后发送 POST 请求，请求头带上加密所用的序列号
+    pub(crate) async fn post_sensitive<B>(
+        &self,
+        url_path: &str,
+        body: &B,
+    ) -> Result<String, WxpayApiError>
+    where
+        B: SensitiveFields + Serialize + Clone,
+    {
+        let (serial_no, public_key) = self.newest_platform_public_key().await?;
+        let mut body = body.clone();
+        body.encrypt_sensitive_fields(&public_key)?;
+        let body = serde_json::to_string(&body)?;
+        self.send(
+            reqwest::Method::POST,
+            url_path,
+            Some(body),
+            Some(&serial_no),
+        )
+        .await
+    }
+}
+
+#[test]
+fn test_sensitive_field() {
+    let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+    let public_key = RsaPublicKey::from(&private_key);
+
+    let mut field = SensitiveField::from("张三");
+    assert!(serde_json::to_string(&field).is_err());
+    assert_eq!(format!("{:?}", field), "Plain(***)");
+
+    field.encrypt(&public_key).unwrap();
+    let json = serde_json::to_string(&field).unwrap();
+    let field: SensitiveField = serde_json::from_str(&json).unwrap();
+    let SensitiveField::Encrypted(ciphertext) = field else {
+        panic!("field should be encrypted");
+    };
+    assert_eq!(
+        decrypt_sensitive(&private_key, &ciphertext).unwrap(),
+        "张三"
+    );
+}
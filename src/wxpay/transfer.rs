@@ -0,0 +1,302 @@
+//! [商家转账](https://pay.weixin.qq.com/doc/v3/merchant/4012711988)（新版 transfer-bills 接口）
+//!
+//! 旧版的[批量转账](super::api::request_batch_transfer)已不再对新商户开放。新版转账需要用户在小程序或公众号中确认收款：
+//!
+//! ```ignore
+//! let res_data = client.request_transfer_bill(&body).await?;
+//! if let Some(params) = client.merchant_transfer_params(appid, &res_data) {
+//!     // 将 params 返回给小程序，调用 wx.requestMerchantTransfer
+//! }
+//! ```
+//!
+//! 和 [`api`](super::api) 一样，每个接口也提供了独立的请求函数，如 [`request_transfer_bill`]。
+use chrono::{DateTime, FixedOffset};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    api::{temporary_client, WXPAY_DEFAULT_BASE_URL},
+    client::WxpayClient,
+    error::WxpayApiError,
+    sensitive::{SensitiveField, SensitiveFields},
+    utils::parse_public_key,
+};
+
+/// 转账单状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransferBillState {
+    /// 转账已受理
+    Accepted,
+    /// 转账锁定资金中
+    Processing,
+    /// 待收款用户确认，可拉起微信收款确认页面进行收款确认
+    WaitUserConfirm,
+    /// 转账中
+    Transfering,
+    /// 转账成功
+    Success,
+    /// 转账失败
+    Fail,
+    /// 商户撤销请求受理成功，该笔转账正在撤销中
+    Canceling,
+    /// 转账撤销完成
+    Cancelled,
+    /// 微信支付新增的未知状态
+    #[serde(other)]
+    Unknown,
+}
+
+/// 转账场景报备信息
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TransferSceneReportInfo {
+    /// 信息类型，如 `活动名称`、`奖励说明`，需与转账场景要求的报备信息一致
+    pub info_type: String,
+    pub info_content: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TransferBillRequestBody {
+    pub appid: String,
+    /// 商户系统内部的商家单号，只能由数字、大小写字母组成
+    pub out_bill_no: String,
+    /// 转账场景ID
+    pub transfer_scene_id: String,
+    pub openid: String,
+    /// 收款用户姓名，转账金额 >= 2000 元时必填，需要加密
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_name: Option<SensitiveField>,
+    /// 转账金额，单位为分
+    pub transfer_amount: i64,
+    /// 转账备注，用户收款时可见，UTF8 编码最多 32 个字符
+    pub transfer_remark: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify_url: Option<String>,
+    /// 用户收款感知，如 `现金奖励`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_recv_perception: Option<String>,
+    pub transfer_scene_report_infos: Vec<TransferSceneReportInfo>,
+}
+
+impl SensitiveFields for TransferBillRequestBody {
+    fn sensitive_fields(&mut self) -> Vec<&mut SensitiveField> {
+        self.user_name.iter_mut().collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TransferBillResponseData {
+    pub out_bill_no: String,
+    /// 微信转账单号
+    pub transfer_bill_no: String,
+    pub create_time: DateTime<FixedOffset>,
+    pub state: TransferBillState,
+    pub fail_reason: Option<String>,
+    /// 状态为 [`TransferBillState::WaitUserConfirm`] 时返回，用于拉起用户确认收款页面
+    pub package_info: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CancelTransferBillResponseData {
+    pub out_bill_no: String,
+    pub transfer_bill_no: String,
+    pub state: TransferBillState,
+    pub update_time: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TransferBillDetailResponseData {
+    pub mch_id: String,
+    pub out_bill_no: String,
+    pub transfer_bill_no: String,
+    pub appid: String,
+    pub state: TransferBillState,
+    pub transfer_amount: i64,
+    pub transfer_remark: String,
+    pub fail_reason: Option<String>,
+    pub openid: Option<String>,
+    /// 收款用户姓名，使用 [`WxpayClient::decrypt_sensitive`] 解密
+    pub user_name: Option<SensitiveField>,
+    pub create_time: DateTime<FixedOffset>,
+    pub update_time: DateTime<FixedOffset>,
+}
+
+/// [商家转账回调通知](https://pay.weixin.qq.com/doc/v3/merchant/4012712115)（MCHTRANSFER.BILL.FINISHED）解密后的数据
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TransferBillCallbackResourceData {
+    pub out_bill_no: String,
+    pub transfer_bill_no: String,
+    pub state: TransferBillState,
+    pub mch_id: String,
+    pub transfer_amount: i64,
+    pub openid: String,
+    pub fail_reason: Option<String>,
+    pub create_time: DateTime<FixedOffset>,
+    pub update_time: DateTime<FixedOffset>,
+}
+
+/// 小程序 [`wx.requestMerchantTransfer`](https://pay.weixin.qq.com/doc/v3/merchant/4012716430) 所需的参数
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerchantTransferParams {
+    pub mch_id: String,
+    pub app_id: String,
+    /// 发起转账返回的 `package_info`
+    pub package: String,
+}
+
+/// [发起转账](https://pay.weixin.qq.com/doc/v3/merchant/4012716434)
+#[bon::builder]
+pub async fn request_transfer_bill<'a>(
+    body: TransferBillRequestBody,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 微信支付平台证书序列号或微信支付公钥 ID，`user_name` 需要加密时必填
+    wxpay_serial_no: Option<&'a str>,
+    // 微信支付平台证书公钥或微信支付公钥，传入时会使用它加密明文的 `user_name`
+    wx_public_key: Option<&'a str>,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<TransferBillResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    let mut body = body;
+    if let Some(wx_public_key) = wx_public_key {
+        body.encrypt_sensitive_fields(&parse_public_key(wx_public_key)?)?;
+    }
+    let body = serde_json::to_string(&body)?;
+    let text = client
+        .send(
+            Method::POST,
+            "/v3/fund-app/mch-transfer/transfer-bills",
+            Some(body),
+            wxpay_serial_no,
+        )
+        .await?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+/// [撤销转账](https://pay.weixin.qq.com/doc/v3/merchant/4012716458)
+#[bon::builder]
+pub async fn request_cancel_transfer_bill<'a>(
+    out_bill_no: &'a str,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<CancelTransferBillResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client.request_cancel_transfer_bill(out_bill_no).await
+}
+
+/// [商户单号查询转账单](https://pay.weixin.qq.com/doc/v3/merchant/4012716437)
+#[bon::builder]
+pub async fn request_transfer_bill_by_out_bill_no<'a>(
+    out_bill_no: &'a str,
+    mchid: &'a str,
+    mch_private_key: &'a str,
+    mch_serial_no: &'a str,
+    // 请求的协议和域名，默认为 `WXPAY_DEFAULT_BASE_URL`，可在测试中指向本地的 mock 服务
+    #[builder(default = WXPAY_DEFAULT_BASE_URL)] base_url: &'a str,
+    // 发起请求所用的 http client，未指定时使用进程内共享的 client
+    http_client: Option<&'a reqwest::Client>,
+) -> Result<TransferBillDetailResponseData, WxpayApiError> {
+    let client = temporary_client(mchid, mch_private_key, mch_serial_no, base_url, http_client)?;
+    client
+        .request_transfer_bill_by_out_bill_no(out_bill_no)
+        .await
+}
+
+/// 生成 `wx.requestMerchantTransfer` 所需的参数，转账不需要用户确认（没有 `package_info`）时返回 `None`
+pub fn merchant_transfer_params(
+    mchid: &str,
+    app_id: &str,
+    res_data: &TransferBillResponseData,
+) -> Option<MerchantTransferParams> {
+    res_data
+        .package_info
+        .as_ref()
+        .map(|package_info| MerchantTransferParams {
+            mch_id: mchid.into(),
+            app_id: app_id.into(),
+            package: package_info.clone(),
+        })
+}
+
+impl WxpayClient {
+    /// [发起转账](https://pay.weixin.qq.com/doc/v3/merchant/4012716434)，使用当前的平台证书自动加密 `user_name`
+    pub async fn request_transfer_bill(
+        &self,
+        body: &TransferBillRequestBody,
+    ) -> Result<TransferBillResponseData, WxpayApiError> {
+        let text = self
+            .post_sensitive("/v3/fund-app/mch-transfer/transfer-bills", body)
+            .await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// [撤销转账](https://pay.weixin.qq.com/doc/v3/merchant/4012716458)，仅在用户确认收款前可以撤销
+    pub async fn request_cancel_transfer_bill(
+        &self,
+        out_bill_no: &str,
+    ) -> Result<CancelTransferBillResponseData, WxpayApiError> {
+        let text = self
+            .send(
+                Method::POST,
+                &format!(
+                    "/v3/fund-app/mch-transfer/transfer-bills/out-bill-no/{}/cancel",
+                    out_bill_no
+                ),
+                None,
+                None,
+            )
+            .await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// [商户单号查询转账单](https://pay.weixin.qq.com/doc/v3/merchant/4012716437)
+    pub async fn request_transfer_bill_by_out_bill_no(
+        &self,
+        out_bill_no: &str,
+    ) -> Result<TransferBillDetailResponseData, WxpayApiError> {
+        self.get(&format!(
+            "/v3/fund-app/mch-transfer/transfer-bills/out-bill-no/{}",
+            out_bill_no
+        ))
+        .await
+    }
+
+    /// 生成 `wx.requestMerchantTransfer` 所需的参数，转账不需要用户确认（没有 `package_info`）时返回 `None`
+    pub fn merchant_transfer_params(
+        &self,
+        app_id: &str,
+        res_data: &TransferBillResponseData,
+    ) -> Option<MerchantTransferParams> {
+        merchant_transfer_params(self.mchid(), app_id, res_data)
+    }
+}
+
+#[test]
+fn test_deserialize_transfer_bill_response() {
+    let data: TransferBillResponseData = serde_json::from_str(
+        r#"{"out_bill_no":"plfk2020042013","transfer_bill_no":"1330000071100999991182020050700019480001","create_time":"2015-05-20T13:29:35.120+08:00","state":"WAIT_USER_CONFIRM","package_info":"affffddafdfafddffda=="}"#,
+    )
+    .unwrap();
+    assert_eq!(data.state, TransferBillState::WaitUserConfirm);
+    assert_eq!(data.package_info.as_deref(), Some("affffddafdfafddffda=="));
+
+    let data: TransferBillCallbackResourceData = serde_json::from_str(
+        r#"{"out_bill_no":"plfk2020042013","transfer_bill_no":"1330000071100999991182020050700019480001","state":"TRANSFERING","mch_id":"1900001109","transfer_amount":2000,"openid":"o-MYE42l80oelYMDE34nYD456Xoy","create_time":"2015-05-20T13:29:35.120+08:00","update_time":"2015-05-20T13:29:35.120+08:00"}"#,
+    )
+    .unwrap();
+    assert_eq!(data.state, TransferBillState::Transfering);
+    assert_eq!(
+        data.update_time.to_rfc3339(),
+        "2015-05-20T13:29:35.120+08:00"
+    );
+}
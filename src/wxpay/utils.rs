@@ -1,6 +1,9 @@
 use base64::{engine::general_purpose, Engine};
-use rsa::{pkcs8::DecodePrivateKey, Pkcs1v15Sign};
-use serde::{Deserialize, Serialize};
+use rsa::{
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+    Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey,
+};
+use serde::Serialize;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use super::error::WxpayApiError;
@@ -12,11 +15,18 @@ pub fn generate_wxpay_request_signature(
     private_key: &str,
     body: Option<&str>,
 ) -> Result<(String, String, String), WxpayApiError> {
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
-        .to_string();
+    let private_key = parse_private_key(private_key)?;
+    Ok(sign_wxpay_request(method, url_path, &private_key, body))
+}
+
+/// 使用已解析的商户私钥生成请求签名，返回 (签名, 时间戳, 随机字符串)
+pub(crate) fn sign_wxpay_request(
+    method: &str,
+    url_path: &str,
+    private_key: &RsaPrivateKey,
+    body: Option<&str>,
+) -> (String, String, String) {
+    let timestamp = unix_timestamp();
     let nonce_str = generate_noncestr(32);
 
     let mut content_to_sign = format!("{}\n{}\n{}\n{}\n", method, url_path, timestamp, nonce_str);
@@ -26,9 +36,9 @@ pub fn generate_wxpay_request_signature(
     }
     content_to_sign.push('\n');
 
-    let signature = sha256_ras_and_base64(private_key, &content_to_sign)?;
+    let signature = sign_sha256_rsa(private_key, &content_to_sign);
 
-    Ok((signature, timestamp, nonce_str))
+    (signature, timestamp, nonce_str)
 }
 
 /// 生成微信支付调起支付签名
@@ -37,11 +47,17 @@ pub fn generate_wxpay_pay_signature(
     prepay_id: &str,
     private_key: &str,
 ) -> Result<(String, String, String), WxpayApiError> {
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
-        .to_string();
+    let private_key = parse_private_key(private_key)?;
+    Ok(sign_wxpay_pay(app_id, prepay_id, &private_key))
+}
+
+/// 使用已解析的商户私钥生成调起支付签名，返回 (签名, 时间戳, 随机字符串)
+pub(crate) fn sign_wxpay_pay(
+    app_id: &str,
+    prepay_id: &str,
+    private_key: &RsaPrivateKey,
+) -> (String, String, String) {
+    let timestamp = unix_timestamp();
     let nonce_str = generate_noncestr(32);
 
     let content_to_sign = format!(
@@ -49,9 +65,108 @@ pub fn generate_wxpay_pay_signature(
         app_id, timestamp, nonce_str, prepay_id
     );
 
-    let signature = sha256_ras_and_base64(private_key, &content_to_sign)?;
+    let signature = sign_sha256_rsa(private_key, &content_to_sign);
+
+    (signature, timestamp, nonce_str)
+}
 
-    Ok((signature, timestamp, nonce_str))
+/// JSAPI/小程序调起支付所需的参数，可以直接序列化后传给 `wx.requestPayment` 或 `WeixinJSBridge.invoke('getBrandWCPayRequest')`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WxpayJsapiPayParams {
+    pub app_id: String,
+    pub time_stamp: String,
+    pub nonce_str: String,
+    /// `prepay_id=xxx`
+    pub package: String,
+    /// 固定为 `RSA`
+    pub sign_type: String,
+    pub pay_sign: String,
+}
+
+/// 生成 [JSAPI/小程序调起支付](https://pay.weixin.qq.com/doc/v3/merchant/4012791898)所需的参数
+pub fn generate_wxpay_jsapi_pay_params(
+    app_id: &str,
+    prepay_id: &str,
+    private_key: &str,
+) -> Result<WxpayJsapiPayParams, WxpayApiError> {
+    let private_key = parse_private_key(private_key)?;
+    Ok(sign_wxpay_jsapi_pay(app_id, prepay_id, &private_key))
+}
+
+/// 使用已解析的商户私钥生成 JSAPI/小程序调起支付参数
+pub(crate) fn sign_wxpay_jsapi_pay(
+    app_id: &str,
+    prepay_id: &str,
+    private_key: &RsaPrivateKey,
+) -> WxpayJsapiPayParams {
+    let (signature, timestamp, nonce_str) = sign_wxpay_pay(app_id, prepay_id, private_key);
+    WxpayJsapiPayParams {
+        app_id: app_id.into(),
+        time_stamp: timestamp,
+        nonce_str,
+        package: format!("prepay_id={}", prepay_id),
+        sign_type: "RSA".into(),
+        pay_sign: signature,
+    }
+}
+
+/// APP 调起支付所需的参数，字段名和客户端 SDK 的 PayReq 一致
+#[derive(Debug, Clone, Serialize)]
+pub struct WxpayAppPayParams {
+    pub appid: String,
+    /// 商户号
+    pub partnerid: String,
+    pub prepayid: String,
+    /// 固定为 `Sign=WXPay`
+    pub package: String,
+    pub noncestr: String,
+    pub timestamp: String,
+    pub sign: String,
+}
+
+/// 生成 [APP 调起支付](https://pay.weixin.qq.com/doc/v3/merchant/4013070351)所需的参数
+pub fn generate_wxpay_app_pay_params(
+    app_id: &str,
+    mchid: &str,
+    prepay_id: &str,
+    private_key: &str,
+) -> Result<WxpayAppPayParams, WxpayApiError> {
+    let private_key = parse_private_key(private_key)?;
+    Ok(sign_wxpay_app_pay(app_id, mchid, prepay_id, &private_key))
+}
+
+/// 使用已解析的商户私钥生成 APP 调起支付参数，签名串中的 prepay_id 不带 `prepay_id=` 前缀
+pub(crate) fn sign_wxpay_app_pay(
+    app_id: &str,
+    mchid: &str,
+    prepay_id: &str,
+    private_key: &RsaPrivateKey,
+) -> WxpayAppPayParams {
+    let timestamp = unix_timestamp();
+    let nonce_str = generate_noncestr(32);
+
+    let content_to_sign = format!("{}\n{}\n{}\n{}\n", app_id, timestamp, nonce_str, prepay_id);
+
+    let signature = sign_sha256_rsa(private_key, &content_to_sign);
+
+    WxpayAppPayParams {
+        appid: app_id.into(),
+        partnerid: mchid.into(),
+        prepayid: prepay_id.into(),
+        package: "Sign=WXPay".into(),
+        noncestr: nonce_str,
+        timestamp,
+        sign: signature,
+    }
+}
+
+fn unix_timestamp() -> String {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string()
 }
 
 /// 随机字符串生成
@@ -70,22 +185,33 @@ pub fn sha256_ras_and_base64(
     private_key: &str,
     content_to_be_signed: &str,
 ) -> Result<String, WxpayApiError> {
+    let private_key = parse_private_key(private_key)?;
+    Ok(sign_sha256_rsa(&private_key, content_to_be_signed))
+}
+
+/// 解析商户私钥
+pub(crate) fn parse_private_key(private_key: &str) -> Result<RsaPrivateKey, WxpayApiError> {
+    // 这里使用 from_pkcs8_pem 还是 from_pkcs1_pem 可以看你密钥文件的文件开头格式
+    RsaPrivateKey::from_pkcs8_pem(private_key).map_err(|_| WxpayApiError::InvalidPrivateKey)
+}
+
+/// 解析微信支付平台证书公钥或微信支付公钥
+pub(crate) fn parse_public_key(public_key: &str) -> Result<RsaPublicKey, WxpayApiError> {
+    RsaPublicKey::from_public_key_pem(public_key).map_err(|_| WxpayApiError::InvalidPublicKey)
+}
+
+pub(crate) fn sign_sha256_rsa(private_key: &RsaPrivateKey, content_to_be_signed: &str) -> String {
     use rsa::sha2::{Digest, Sha256};
-    use rsa::RsaPrivateKey;
 
     let mut hasher = Sha256::new();
     hasher.update(content_to_be_signed);
     let hash = hasher.finalize();
 
-    // 这里使用 from_pkcs8_pem 还是 from_pkcs1_pem 可以看你密钥文件的文件开头格式
-    let private_key =
-        RsaPrivateKey::from_pkcs8_pem(private_key).map_err(|e| WxpayApiError::InvalidPrivateKey)?;
     // Pkcs1v15Sign 是使用 PKCS#1 v1.5 规范进行签名，还有个 SigningKey 是用于生产签名所用的秘钥的，并不是用来签名的，所以这里不能用错..
     let padding = Pkcs1v15Sign::new::<rsa::sha2::Sha256>();
     let signature = private_key.sign(padding, &hash).expect("failed to sign");
 
-    let sig = general_purpose::STANDARD.encode(&signature);
-    Ok(sig)
+    general_purpose::STANDARD.encode(signature)
 }
 
 #[test]
@@ -106,3 +232,51 @@ fn test_generate_wxpay_signature() {
         Err(e) => println!("生成签名失败: {}", e),
     }
 }
+
+#[test]
+fn test_sign_wxpay_app_pay() {
+    use rsa::sha2::{Digest, Sha256};
+
+    let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+    let params = sign_wxpay_app_pay(
+        "wx8888888888888888",
+        "1900000109",
+        "WX1217752501201407033233368018",
+        &private_key,
+    );
+    assert_eq!(params.package, "Sign=WXPay");
+    assert_eq!(params.partnerid, "1900000109");
+
+    let message = format!(
+        "wx8888888888888888\n{}\n{}\nWX1217752501201407033233368018\n",
+        params.timestamp, params.noncestr
+    );
+    let signature = general_purpose::STANDARD.decode(&params.sign).unwrap();
+    assert!(RsaPublicKey::from(&private_key)
+        .verify(
+            Pkcs1v15Sign::new::<Sha256>(),
+            &Sha256::digest(message),
+            &signature
+        )
+        .is_ok());
+}
+
+#[test]
+fn test_wxpay_jsapi_pay_params_serialize() {
+    let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+    let params = sign_wxpay_jsapi_pay(
+        "wx8888888888888888",
+        "wx201410272009395522657a690389285100",
+        &private_key,
+    );
+    let value = serde_json::to_value(&params).unwrap();
+    assert_eq!(value["appId"], "wx8888888888888888");
+    assert_eq!(
+        value["package"],
+        "prepay_id=wx201410272009395522657a690389285100"
+    );
+    assert_eq!(value["signType"], "RSA");
+    assert_eq!(value["timeStamp"], params.time_stamp);
+    assert_eq!(value["nonceStr"], params.nonce_str);
+    assert_eq!(value["paySign"], params.pay_sign);
+}